@@ -0,0 +1,234 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::agent::Attribution;
+use crate::memory::{get_repo_name, redact_secrets};
+
+/// One line of the activity log.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Entry {
+    pub time: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: Event,
+    /// The agent behind the operation; absent when run by a human
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<Attribution>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A run of agntz itself
+    Invocation {
+        /// Subcommand path (`memory add`)
+        command: String,
+        /// Full argument list, with secrets redacted
+        args: Vec<String>,
+        ok: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        duration_ms: u64,
+    },
+    /// A change made through a backend that can't store who made it
+    Backend {
+        /// Backend tool the operation went to (`trx`, `skdlr`)
+        backend: String,
+        args: Vec<String>,
+        ok: bool,
+    },
+}
+
+/// Record one run of agntz.
+pub fn record_invocation(command: &str, duration: Duration, result: &Result<()>) {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    record(
+        Event::Invocation {
+            command: command.to_string(),
+            args: redact(&args),
+            ok: result.is_ok(),
+            error: result
+                .as_ref()
+                .err()
+                .map(|err| redact_secrets(&format!("{:#}", err))),
+            duration_ms: duration.as_millis() as u64,
+        },
+        Attribution::detect(),
+    );
+}
+
+/// Record a backend operation on behalf of an agent, for backends that
+/// can't store who made a change themselves.
+pub fn record_backend<S: AsRef<str>>(backend: &str, args: &[S], agent: Attribution, ok: bool) {
+    record(
+        Event::Backend {
+            backend: backend.to_string(),
+            args: redact(args),
+            ok,
+        },
+        Some(agent),
+    );
+}
+
+/// Logging never fails the operation itself; problems are only reported.
+fn record(event: Event, agent: Option<Attribution>) {
+    let entry = Entry {
+        time: Utc::now(),
+        event,
+        agent,
+        repo: get_repo_name(),
+    };
+    if let Err(err) = append(&entry) {
+        eprintln!("warning: could not write activity log: {:#}", err);
+    }
+}
+
+fn redact<S: AsRef<str>>(args: &[S]) -> Vec<String> {
+    args.iter().map(|a| redact_secrets(a.as_ref())).collect()
+}
+
+fn append(entry: &Entry) -> Result<()> {
+    let path = log_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+fn log_path() -> Result<PathBuf> {
+    let state = dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .context("could not determine state directory")?;
+    Ok(state.join("agntz").join("activity.jsonl"))
+}
+
+/// Read the log, oldest first, skipping lines that don't parse.
+pub fn read() -> Result<Vec<Entry>> {
+    let path = log_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// `agntz log`: show recent activity, newest last.
+pub fn handle_log(
+    since: Option<DateTime<Utc>>,
+    agent: Option<String>,
+    limit: usize,
+    json: bool,
+) -> Result<()> {
+    let mut entries: Vec<Entry> = read()?
+        .into_iter()
+        .filter(|e| since.is_none_or(|since| e.time >= since))
+        .filter(|e| match &agent {
+            Some(name) => e
+                .agent
+                .as_ref()
+                .is_some_and(|a| a.agent.eq_ignore_ascii_case(name)),
+            None => true,
+        })
+        .collect();
+    if entries.len() > limit {
+        entries.drain(..entries.len() - limit);
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    if entries.is_empty() {
+        println!("No activity recorded");
+        return Ok(());
+    }
+
+    for entry in &entries {
+        println!("{}", format_entry(entry));
+    }
+    Ok(())
+}
+
+fn format_entry(entry: &Entry) -> String {
+    let time = entry.time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S");
+    let who = match &entry.agent {
+        Some(agent) => match agent.meta.get("session").and_then(|s| s.as_str()) {
+            Some(session) => format!("{} ({})", agent.agent, short(session)),
+            None => agent.agent.clone(),
+        },
+        None => "human".to_string(),
+    };
+    let repo = entry.repo.as_deref().unwrap_or("-");
+
+    match &entry.event {
+        Event::Invocation {
+            args,
+            ok,
+            error,
+            duration_ms,
+            ..
+        } => {
+            let status = match (ok, error) {
+                (true, _) => "ok".to_string(),
+                (false, Some(error)) => format!("failed: {}", error.lines().next().unwrap_or("")),
+                (false, None) => "failed".to_string(),
+            };
+            format!(
+                "{}  {:<20} {:<12} agntz {}  [{}, {}ms]",
+                time,
+                who,
+                repo,
+                quote(args),
+                status,
+                duration_ms
+            )
+        }
+        Event::Backend { backend, args, ok } => format!(
+            "{}  {:<20} {:<12}   -> {} {}  [{}]",
+            time,
+            who,
+            repo,
+            backend,
+            quote(args),
+            if *ok { "ok" } else { "failed" }
+        ),
+    }
+}
+
+fn short(session: &str) -> &str {
+    match session.char_indices().nth(8) {
+        Some((i, _)) => &session[..i],
+        None => session,
+    }
+}
+
+/// Join arguments for display, quoting the ones a shell would split.
+fn quote(args: &[String]) -> String {
+    args.iter()
+        .map(|a| {
+            if a.is_empty() || a.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'') {
+                format!("{:?}", a)
+            } else {
+                a.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
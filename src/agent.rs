@@ -0,0 +1,426 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+/// Who an operation is attributed to, as handed to backends.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Attribution {
+    pub agent: String,
+    /// Repo, session and model context
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub meta: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Attribution {
+    pub fn detect() -> Option<Attribution> {
+        static DETECTED: OnceLock<Option<Attribution>> = OnceLock::new();
+        DETECTED.get_or_init(Attribution::detect_uncached).clone()
+    }
+
+    fn detect_uncached() -> Option<Attribution> {
+        let identity = detect()?;
+
+        let mut meta = serde_json::Map::new();
+        if let Some(repo) = crate::memory::get_repo_name() {
+            meta.insert("repo".to_string(), serde_json::Value::String(repo));
+        }
+        if let Some(id) = identity
+            .session_id
+            .filter(|id| Some(id) != identity.session.as_ref())
+        {
+            meta.insert("session_id".to_string(), serde_json::Value::String(id));
+        }
+        if let Some(session) = identity.session {
+            meta.insert("session".to_string(), serde_json::Value::String(session));
+        }
+        if let Some(model) = identity.model {
+            meta.insert("model".to_string(), serde_json::Value::String(model));
+        }
+
+        Some(Attribution {
+            agent: identity.harness,
+            meta,
+        })
+    }
+
+    fn meta_str(&self, key: &str) -> Option<&str> {
+        self.meta.get(key).and_then(|v| v.as_str())
+    }
+
+    /// Whether this was recorded in `session`, given by name or ID.
+    pub fn in_session(&self, session: &str) -> bool {
+        self.meta_str("session") == Some(session) || self.meta_str("session_id") == Some(session)
+    }
+
+    /// Attach this identity to a command for `backend`.
+    ///
+    /// Returns false when the backend has no way to store it.
+    pub fn apply(&self, backend: &str, cmd: &mut Command) -> bool {
+        // Pass detected identities on in the AGENT_* convention, so hooks
+        // and tools further down see the same agent we did
+        if env("AGENT_HARNESS").is_none() {
+            cmd.env("AGENT_HARNESS", &self.agent);
+            let session_id = self.meta_str("session_id").or(self.meta_str("session"));
+            if let Some(id) = session_id {
+                cmd.env("AGENT_SESSION_ID", id);
+            }
+            if let Some(model) = self.meta_str("model") {
+                cmd.env("AGENT_MODEL", model);
+            }
+        }
+
+        let Some((_, prefix)) = NATIVE_ATTRIBUTION.iter().find(|(b, _)| *b == backend) else {
+            return false;
+        };
+        cmd.env(format!("{}_AGENT", prefix), &self.agent);
+        cmd.env(format!("{}_AGENT_KIND", prefix), "coding_agent");
+        cmd.env_remove(format!("{}_AGENT_META", prefix));
+        if !self.meta.is_empty() {
+            if let Ok(meta_json) = serde_json::to_string(&self.meta) {
+                cmd.env(format!("{}_AGENT_META", prefix), meta_json);
+            }
+        }
+        true
+    }
+}
+
+/// Attribute a backend command to the detected agent, if any.
+///
+/// Returns the attribution when the backend can't store it, so the caller
+/// can record it in the activity log instead.
+pub fn attribute(backend: &str, cmd: &mut Command) -> Option<Attribution> {
+    let attribution = Attribution::detect()?;
+    if attribution.apply(backend, cmd) {
+        None
+    } else {
+        Some(attribution)
+    }
+}
+
+/// Agent identity detected from the environment.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentIdentity {
+    /// Harness name (e.g. "pi", "opencode")
+    pub harness: String,
+    /// Session name, or ID if the session has no name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    /// Raw session ID, as other tools (hstry) know the session
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// Model string (e.g. "anthropic/claude-sonnet-4")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// What the detection was based on, for `agntz whoami`
+    pub evidence: Vec<String>,
+}
+
+/// A way of recognising the harness we are running under.
+struct Detector {
+    name: &'static str,
+    detect: fn() -> Option<AgentIdentity>,
+}
+
+/// Tried in order; the first match wins. Explicit `AGENT_*` variables come
+/// first so any harness can override the guesses below.
+const DETECTORS: &[Detector] = &[
+    Detector {
+        name: "AGENT_HARNESS",
+        detect: from_agent_env,
+    },
+    Detector {
+        name: "harness env vars",
+        detect: from_harness_env,
+    },
+    Detector {
+        name: "parent processes",
+        detect: from_parent_processes,
+    },
+];
+
+/// Environment variables that known harnesses set for the commands they run.
+const HARNESS_ENV: &[(&str, &str)] = &[
+    ("CLAUDECODE", "claude-code"),
+    ("OPENCODE", "opencode"),
+    ("CODEX_SANDBOX", "codex"),
+    ("GEMINI_CLI", "gemini-cli"),
+];
+
+/// Executable names of known harnesses, matched exactly against the program
+/// (or script) ancestor processes run.
+const HARNESS_PROCESSES: &[(&str, &str)] = &[
+    ("claude", "claude-code"),
+    ("opencode", "opencode"),
+    ("codex", "codex"),
+    ("gemini", "gemini-cli"),
+    ("aider", "aider"),
+    ("goose", "goose"),
+    ("amp", "amp"),
+    ("cursor-agent", "cursor"),
+    ("pi", "pi"),
+];
+
+/// Programs that run harnesses written as scripts.
+const INTERPRETERS: &[&str] = &["node", "bun", "deno", "python", "python3"];
+
+/// Finds the transcript of the running session for one harness.
+type SessionFileFinder = fn() -> Option<PathBuf>;
+
+/// Where harnesses keep session transcripts, used to find the session ID
+/// when the harness doesn't export one.
+const SESSION_FILES: &[(&str, SessionFileFinder)] = &[
+    ("claude-code", claude_session_file),
+    ("codex", codex_session_file),
+];
+
+/// Backends that store attribution themselves, and the prefix of the
+/// `<PREFIX>_AGENT`, `<PREFIX>_AGENT_KIND` and `<PREFIX>_AGENT_META`
+/// variables they read.
+const NATIVE_ATTRIBUTION: &[(&str, &str)] = &[("mmry", "MMRY")];
+
+/// Transcripts older than this belong to some other, finished session.
+const SESSION_FILE_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Detect the agent harness we are running under, if any.
+///
+/// Walking the process tree takes a dozen lookups and every backend call
+/// asks, so the result is computed once per process.
+pub fn detect() -> Option<AgentIdentity> {
+    static DETECTED: OnceLock<Option<AgentIdentity>> = OnceLock::new();
+    DETECTED.get_or_init(detect_uncached).clone()
+}
+
+fn detect_uncached() -> Option<AgentIdentity> {
+    let mut identity = DETECTORS.iter().find_map(|d| (d.detect)())?;
+
+    if identity.session.is_none() {
+        let file = SESSION_FILES
+            .iter()
+            .filter(|(harness, _)| *harness == identity.harness)
+            .find_map(|(_, find)| find());
+        if let Some(file) = file {
+            identity.session = file
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string());
+            identity.session_id = identity.session.clone();
+            identity
+                .evidence
+                .push(format!("session file {}", file.display()));
+        }
+    }
+
+    Some(identity)
+}
+
+/// Any harness (pi, opencode, aider, etc.) can set:
+///   AGENT_HARNESS=<name>          (e.g. "pi", "opencode")
+///   AGENT_SESSION_ID=<uuid>
+///   AGENT_SESSION_NAME=<readable> (set after auto-rename)
+///   AGENT_SESSION_FILE=<path>
+///   AGENT_MODEL=<provider>/<id>
+///   AGENT_CWD=<workdir>
+fn from_agent_env() -> Option<AgentIdentity> {
+    let harness = env("AGENT_HARNESS")?;
+    let mut evidence = vec![format!("AGENT_HARNESS={}", harness)];
+
+    let session_id =
+        env("AGENT_SESSION_ID").inspect(|_| evidence.push("AGENT_SESSION_ID".to_string()));
+    // Prefer session name (human-readable) over raw UUID
+    let session = env("AGENT_SESSION_NAME")
+        .inspect(|_| evidence.push("AGENT_SESSION_NAME".to_string()))
+        .or_else(|| session_id.clone());
+
+    Some(AgentIdentity {
+        harness,
+        session,
+        session_id,
+        model: model(&mut evidence),
+        evidence,
+    })
+}
+
+fn from_harness_env() -> Option<AgentIdentity> {
+    let (var, harness) = HARNESS_ENV.iter().find(|(var, _)| env(var).is_some())?;
+    let mut evidence = vec![format!("{} is set", var)];
+
+    let session_id =
+        env("AGENT_SESSION_ID").inspect(|_| evidence.push("AGENT_SESSION_ID".to_string()));
+
+    Some(AgentIdentity {
+        harness: harness.to_string(),
+        session: session_id.clone(),
+        session_id,
+        model: model(&mut evidence),
+        evidence,
+    })
+}
+
+fn from_parent_processes() -> Option<AgentIdentity> {
+    let (mut pid, _) = process_info(std::process::id())?;
+
+    // Shells and wrappers sit between us and the harness; a few levels is plenty
+    for _ in 0..12 {
+        if pid <= 1 {
+            return None;
+        }
+        let (ppid, args) = process_info(pid)?;
+
+        if let Some(harness) = harness_process(&args) {
+            let mut evidence = vec![format!("parent process {} ({})", pid, args.join(" "))];
+            return Some(AgentIdentity {
+                harness: harness.to_string(),
+                session: None,
+                session_id: None,
+                model: model(&mut evidence),
+                evidence,
+            });
+        }
+        pid = ppid;
+    }
+    None
+}
+
+/// The harness a command line runs, going by the executable's name, or the
+/// script's for interpreted harnesses (`node /path/to/claude ...`).
+fn harness_process(args: &[String]) -> Option<&'static str> {
+    let name = |arg: &String| {
+        Path::new(arg)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+    };
+    let mut program = name(args.first()?)?;
+    if INTERPRETERS.contains(&program.as_str()) {
+        program = name(args.get(1)?)?;
+    }
+    HARNESS_PROCESSES
+        .iter()
+        .find(|(process, _)| *process == program)
+        .map(|(_, harness)| *harness)
+}
+
+/// Parent PID and command line of a process.
+fn process_info(pid: u32) -> Option<(u32, Vec<String>)> {
+    // /proc is cheaper and always there on Linux; fall back to ps elsewhere
+    let proc_dir = PathBuf::from(format!("/proc/{}", pid));
+    if proc_dir.exists() {
+        let stat = std::fs::read_to_string(proc_dir.join("stat")).ok()?;
+        // The command name in field 2 may contain spaces, so split after it
+        let ppid = stat
+            .rsplit_once(')')?
+            .1
+            .split_whitespace()
+            .nth(1)?
+            .parse()
+            .ok()?;
+        let cmdline = std::fs::read(proc_dir.join("cmdline")).ok()?;
+        let args = String::from_utf8_lossy(&cmdline)
+            .split('\0')
+            .filter(|arg| !arg.is_empty())
+            .map(str::to_string)
+            .collect();
+        return Some((ppid, args));
+    }
+
+    let output = Command::new("ps")
+        .args(["-o", "ppid=,args=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    let line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let (ppid, args) = line.split_once(char::is_whitespace)?;
+    Some((
+        ppid.trim().parse().ok()?,
+        args.split_whitespace().map(str::to_string).collect(),
+    ))
+}
+
+fn model(evidence: &mut Vec<String>) -> Option<String> {
+    let model = env("AGENT_MODEL").or_else(|| env("ANTHROPIC_MODEL"))?;
+    evidence.push(format!("model from environment ({})", model));
+    Some(model)
+}
+
+fn env(var: &str) -> Option<String> {
+    std::env::var(var).ok().filter(|s| !s.is_empty())
+}
+
+/// Claude Code keeps transcripts in `~/.claude/projects/<cwd with / as ->/`.
+fn claude_session_file() -> Option<PathBuf> {
+    let projects = dirs::home_dir()?.join(".claude").join("projects");
+    let cwd = std::env::current_dir().ok()?;
+
+    // The session may have been started in a parent of the current directory
+    cwd.ancestors().find_map(|dir| {
+        let project = dir.to_string_lossy().replace(['/', '.'], "-");
+        newest_file(&projects.join(project), "jsonl")
+    })
+}
+
+/// Codex keeps transcripts in `~/.codex/sessions/YYYY/MM/DD/rollout-*.jsonl`.
+fn codex_session_file() -> Option<PathBuf> {
+    let mut dir = dirs::home_dir()?.join(".codex").join("sessions");
+    for _ in 0..3 {
+        dir = latest_subdir(&dir)?;
+    }
+    newest_file(&dir, "jsonl")
+}
+
+fn latest_subdir(dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .max()
+}
+
+/// The most recently written file with `extension` in `dir`, if it is recent.
+fn newest_file(dir: &Path, extension: &str) -> Option<PathBuf> {
+    let (modified, path) = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == extension))
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .max()?;
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default();
+    (age <= SESSION_FILE_MAX_AGE).then_some(path)
+}
+
+/// `agntz whoami`: show the detected identity and how it was found.
+pub fn handle_whoami(json: bool) -> Result<()> {
+    let identity = detect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&identity)?);
+        return Ok(());
+    }
+
+    let Some(identity) = identity else {
+        println!("No agent detected");
+        println!(
+            "Checked: {}",
+            DETECTORS
+                .iter()
+                .map(|d| d.name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        return Ok(());
+    };
+
+    println!("harness: {}", identity.harness);
+    println!("session: {}", identity.session.as_deref().unwrap_or("-"));
+    if identity.session_id != identity.session {
+        println!("id:      {}", identity.session_id.as_deref().unwrap_or("-"));
+    }
+    println!("model:   {}", identity.model.as_deref().unwrap_or("-"));
+    println!("evidence:");
+    for item in &identity.evidence {
+        println!("  - {}", item);
+    }
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use toml_edit::{DocumentMut, Item};
+
+use crate::search::SavedSearch;
+
+/// agntz settings, read from `~/.config/agntz/config.toml` (or `$AGNTZ_CONFIG`).
+///
+/// Every section is optional; a missing file means defaults everywhere.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub memory: MemoryConfig,
+    pub search: SearchConfig,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MemoryConfig {
+    /// How the per-repo mmry store name is derived
+    pub store_naming: StoreNaming,
+    /// What `memory add` does with content that looks sensitive
+    pub safety: SafetyConfig,
+    /// Hold memories added by agents until a human accepts them with
+    /// `agntz memory review`
+    pub review: bool,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// Searches saved with `agntz search save`, by name
+    pub saved: BTreeMap<String, SavedSearch>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StoreNaming {
+    /// Repo name from the origin URL (`agntz`)
+    #[default]
+    Basename,
+    /// Owner and repo from the origin URL (`byteowlz-agntz`)
+    OwnerRepo,
+    /// Repo name plus a hash of the checkout path (`agntz-1a2b3c4d`)
+    PathHash,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SafetyConfig {
+    /// API keys, tokens, private keys, credential assignments
+    pub secrets: SafetyAction,
+    /// Email addresses and phone numbers
+    pub personal_data: SafetyAction,
+}
+
+impl Default for SafetyConfig {
+    fn default() -> Self {
+        SafetyConfig {
+            secrets: SafetyAction::Refuse,
+            personal_data: SafetyAction::Warn,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SafetyAction {
+    /// Don't store the memory
+    Refuse,
+    /// Replace the sensitive part with a placeholder
+    Redact,
+    /// Store as-is but print a warning
+    Warn,
+    /// Store as-is
+    Allow,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        if let Ok(path) = std::env::var("AGNTZ_CONFIG") {
+            if !path.is_empty() {
+                return Some(PathBuf::from(path));
+            }
+        }
+        dirs::config_dir().map(|dir| dir.join("agntz").join("config.toml"))
+    }
+
+    pub fn load() -> Result<Config> {
+        let Some(path) = Config::path() else {
+            return Ok(Config::default());
+        };
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("invalid config in {}", path.display()))
+    }
+
+    /// Add or replace the saved search `name`, keeping the rest of the file,
+    /// comments included, as it is.
+    pub fn save_search(name: &str, search: &SavedSearch) -> Result<()> {
+        let path = Config::path().context("could not determine config directory")?;
+        let content = if path.exists() {
+            fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?
+        } else {
+            String::new()
+        };
+        let mut doc: DocumentMut = content
+            .parse()
+            .with_context(|| format!("invalid config in {}", path.display()))?;
+
+        let mut saved = doc.as_table_mut();
+        for key in ["search", "saved"] {
+            let table = saved.entry(key).or_insert(toml_edit::table());
+            saved = table
+                .as_table_mut()
+                .with_context(|| format!("'{}' in {} is not a table", key, path.display()))?;
+            saved.set_implicit(true);
+        }
+        let search = toml_edit::ser::to_document(search)?;
+        saved.insert(name, Item::Table(search.as_table().clone()));
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, doc.to_string())
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+}
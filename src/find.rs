@@ -0,0 +1,283 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::issues::{self, Task};
+use crate::memory::{self, MemoryHit};
+use crate::search::{self, HstrySearchHit};
+use crate::text;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    Memory,
+    Task,
+    Session,
+}
+
+impl Kind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Memory => "memory",
+            Kind::Task => "task",
+            Kind::Session => "session",
+        }
+    }
+}
+
+/// Smoothing constant for reciprocal-rank fusion, as in the original paper.
+const RRF_K: f32 = 60.0;
+
+/// Hits less relevant than this (on a 0 to 1 scale) are left out.
+const MIN_RELEVANCE: f32 = 0.2;
+
+/// A hit from any backend. mmry, hstry and our task matching don't share a
+/// scale, so hits are ranked by their place in each backend's list
+/// (reciprocal-rank fusion), weighted by how relevant the backend found them.
+#[derive(Serialize)]
+struct Found {
+    kind: Kind,
+    id: String,
+    /// Fused score, at most 1.0
+    score: f32,
+    /// The backend's own score
+    source_score: f32,
+    text: String,
+    /// Category, task status or session message, depending on the kind
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    /// The backend's own record
+    item: serde_json::Value,
+}
+
+pub async fn run(query: String, limit: usize, all_workspaces: bool, json: bool) -> Result<()> {
+    let task_query = query.clone();
+    let history_query = query.clone();
+    let (memories, tasks, history) = tokio::join!(
+        memory::search_memories(&query, limit),
+        tokio::task::spawn_blocking(issues::list_tasks),
+        tokio::task::spawn_blocking(move || {
+            search::history(&history_query, limit, all_workspaces)
+        }),
+    );
+
+    let mut found = Vec::new();
+    let mut failed = Vec::new();
+    match memories {
+        Ok(hits) => found.extend(fuse(
+            hits.into_iter().map(from_memory).collect(),
+            Scale::Relative,
+        )),
+        Err(err) => failed.push(("memories (mmry)", err)),
+    }
+    match tasks.map_err(anyhow::Error::from).and_then(|r| r) {
+        Ok(tasks) => found.extend(fuse(match_tasks(tasks, &task_query), Scale::Absolute)),
+        Err(err) => failed.push(("tasks (trx)", err)),
+    }
+    match history.map_err(anyhow::Error::from).and_then(|r| r) {
+        Ok(hits) => found.extend(fuse(
+            hits.into_iter().map(from_history).collect(),
+            Scale::Relative,
+        )),
+        Err(err) => failed.push(("sessions (hstry)", err)),
+    }
+
+    for (source, err) in &failed {
+        eprintln!("warning: could not search {}: {:#}", source, err);
+    }
+    if failed.len() == 3 {
+        anyhow::bail!("search failed in mmry, trx and hstry");
+    }
+
+    // Stable, so equal scores keep memories before tasks before sessions
+    found.sort_by(|a, b| b.score.total_cmp(&a.score));
+    found.truncate(limit);
+
+    if json {
+        let payload = serde_json::json!({ "hits": found });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+        return Ok(());
+    }
+
+    if found.is_empty() {
+        println!("No results found.");
+        return Ok(());
+    }
+    for hit in &found {
+        let detail = hit
+            .detail
+            .as_deref()
+            .map(|d| format!(" ({})", d))
+            .unwrap_or_default();
+        println!(
+            "{:>5.2} {:<7} {}{} - {}",
+            hit.score,
+            hit.kind.as_str(),
+            hit.id,
+            detail,
+            text::compact(&hit.text, 140)
+        );
+    }
+    Ok(())
+}
+
+/// What a backend's scores say about relevance.
+#[derive(Clone, Copy)]
+enum Scale {
+    /// Only how hits compare within one search, as with mmry and hstry
+    Relative,
+    /// From 0 to 1 on their own, as with our task matching
+    Absolute,
+}
+
+/// Score one backend's hits by rank, `(k + 1) / (k + rank)`, times their
+/// relevance: the backend's score, taken relative to its best hit unless the
+/// scale is absolute. A weak match that tops a short list therefore doesn't
+/// tie with another backend's strong ones.
+fn fuse(mut hits: Vec<Found>, scale: Scale) -> Vec<Found> {
+    let best = hits.iter().map(|h| h.source_score).fold(0.0, f32::max);
+    hits.sort_by(|a, b| b.source_score.total_cmp(&a.source_score));
+    hits.into_iter()
+        .enumerate()
+        .filter_map(|(rank, mut hit)| {
+            let relevance = match scale {
+                Scale::Absolute => hit.source_score,
+                Scale::Relative if best > 0.0 => hit.source_score / best,
+                Scale::Relative => 1.0,
+            }
+            .clamp(0.0, 1.0);
+            if relevance < MIN_RELEVANCE {
+                return None;
+            }
+            hit.score = relevance * (RRF_K + 1.0) / (RRF_K + rank as f32 + 1.0);
+            Some(hit)
+        })
+        .collect()
+}
+
+fn from_memory(hit: MemoryHit) -> Found {
+    Found {
+        kind: Kind::Memory,
+        id: hit.memory.id.clone().unwrap_or_else(|| "-".to_string()),
+        score: 0.0,
+        source_score: hit.score,
+        text: hit.memory.content.clone(),
+        detail: hit.memory.category.clone(),
+        item: serde_json::to_value(&hit.memory).unwrap_or_default(),
+    }
+}
+
+fn from_history(hit: HstrySearchHit) -> Found {
+    let session = hit
+        .external_id
+        .clone()
+        .unwrap_or_else(|| hit.conversation_id.clone());
+    Found {
+        kind: Kind::Session,
+        id: session,
+        score: 0.0,
+        source_score: hit.score,
+        text: hit.snippet.clone(),
+        detail: Some(format!("#{} {}", hit.message_idx, hit.role)),
+        item: serde_json::to_value(&hit).unwrap_or_default(),
+    }
+}
+
+/// trx has no search, so tasks are scored here: the share of query terms
+/// found, with title matches counting double.
+fn match_tasks(tasks: Vec<Task>, query: &str) -> Vec<Found> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|t| t.to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    if terms.is_empty() {
+        return Vec::new();
+    }
+
+    tasks
+        .into_iter()
+        .filter_map(|task| {
+            let title = task.title.to_lowercase();
+            let description = task.description.as_deref().unwrap_or("").to_lowercase();
+            let points: usize = terms
+                .iter()
+                .map(|t| {
+                    let in_title = usize::from(title.contains(t.as_str()));
+                    let in_description = usize::from(description.contains(t.as_str()));
+                    2 * in_title + in_description
+                })
+                .sum();
+            let exact_id = task.id.eq_ignore_ascii_case(query.trim());
+            if points == 0 && !exact_id {
+                return None;
+            }
+            let score = if exact_id {
+                1.0
+            } else {
+                points as f32 / (3 * terms.len()) as f32
+            };
+            Some(Found {
+                kind: Kind::Task,
+                id: task.id.clone(),
+                score: 0.0,
+                source_score: score,
+                text: task.title.clone(),
+                detail: task.status.clone(),
+                item: serde_json::to_value(&task).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(kind: Kind, id: &str, source_score: f32) -> Found {
+        Found {
+            kind,
+            id: id.to_string(),
+            score: 0.0,
+            source_score,
+            text: String::new(),
+            detail: None,
+            item: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn fuse_ranks_within_a_backend() {
+        let hits = fuse(
+            vec![found(Kind::Memory, "b", 2.0), found(Kind::Memory, "a", 4.0)],
+            Scale::Relative,
+        );
+        assert_eq!(hits[0].id, "a");
+        assert_eq!(hits[0].score, 1.0);
+        assert!(hits[1].score < 0.5);
+    }
+
+    #[test]
+    fn fuse_weighs_weak_matches_below_strong_ones() {
+        let memory = fuse(vec![found(Kind::Memory, "m", 0.8)], Scale::Relative);
+        let task = fuse(vec![found(Kind::Task, "t", 0.4)], Scale::Absolute);
+        assert!(task[0].score < memory[0].score);
+    }
+
+    #[test]
+    fn fuse_drops_irrelevant_hits() {
+        let hits = fuse(
+            vec![found(Kind::Task, "t1", 1.0), found(Kind::Task, "t2", 0.1)],
+            Scale::Absolute,
+        );
+        assert_eq!(hits.len(), 1);
+
+        let hits = fuse(
+            vec![
+                found(Kind::Session, "s1", 10.0),
+                found(Kind::Session, "s2", 1.0),
+            ],
+            Scale::Relative,
+        );
+        assert_eq!(hits.len(), 1);
+    }
+}
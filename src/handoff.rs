@@ -0,0 +1,227 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::agent::Attribution;
+use crate::memory;
+
+#[derive(Subcommand)]
+pub enum HandoffCommand {
+    /// Save where this session left off, for the next one on this branch
+    Write {
+        /// What the work is trying to achieve
+        #[arg(short, long)]
+        goal: Option<String>,
+        /// trx task still in progress (repeatable)
+        #[arg(short, long = "task")]
+        tasks: Vec<String>,
+        /// Something still undecided or unknown (repeatable)
+        #[arg(short, long = "question")]
+        questions: Vec<String>,
+        /// What to do next, in order (repeatable)
+        #[arg(short, long = "next")]
+        next_steps: Vec<String>,
+    },
+
+    /// Show the handoff left for this branch
+    Read {
+        /// Branch to read (defaults to the current one)
+        #[arg(short, long)]
+        branch: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Where a session left off, kept per repo and branch.
+#[derive(Debug, Deserialize, Serialize)]
+struct Handoff {
+    branch: String,
+    /// HEAD when the handoff was written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    commit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    goal: Option<String>,
+    #[serde(default)]
+    tasks: Vec<String>,
+    #[serde(default)]
+    questions: Vec<String>,
+    #[serde(default)]
+    next_steps: Vec<String>,
+    /// Uncommitted changes, as `git status --porcelain` lines
+    #[serde(default)]
+    touched_files: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    agent: Option<Attribution>,
+    written_at: DateTime<Utc>,
+}
+
+pub async fn handle(command: HandoffCommand) -> Result<()> {
+    match command {
+        HandoffCommand::Write {
+            goal,
+            tasks,
+            questions,
+            next_steps,
+        } => handle_write(goal, tasks, questions, next_steps),
+        HandoffCommand::Read { branch, json } => handle_read(branch, json),
+    }
+}
+
+fn handle_write(
+    goal: Option<String>,
+    tasks: Vec<String>,
+    questions: Vec<String>,
+    next_steps: Vec<String>,
+) -> Result<()> {
+    if goal.is_none() && tasks.is_empty() && questions.is_empty() && next_steps.is_empty() {
+        anyhow::bail!("nothing to hand off; pass --goal, --task, --question or --next");
+    }
+
+    let branch = current_branch()?;
+    let handoff = Handoff {
+        branch: branch.clone(),
+        commit: git(&["rev-parse", "--short", "HEAD"]),
+        goal,
+        tasks,
+        questions,
+        next_steps,
+        touched_files: git(&["status", "--porcelain"])
+            .map(|out| out.lines().map(|l| l.trim().to_string()).collect())
+            .unwrap_or_default(),
+        agent: Attribution::detect(),
+        written_at: Utc::now(),
+    };
+
+    let path = handoff_path(&branch)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(&handoff)?)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    println!("Wrote handoff for branch {}", branch);
+    Ok(())
+}
+
+fn handle_read(branch: Option<String>, json: bool) -> Result<()> {
+    let branch = match branch {
+        Some(branch) => branch,
+        None => current_branch()?,
+    };
+    let path = handoff_path(&branch)?;
+    if !path.exists() {
+        if json {
+            println!("null");
+        } else {
+            println!("No handoff for branch {}", branch);
+        }
+        return Ok(());
+    }
+
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    let handoff: Handoff = serde_json::from_str(&content)
+        .with_context(|| format!("invalid handoff {}", path.display()))?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&handoff)?);
+        return Ok(());
+    }
+    print!("{}", render(&handoff));
+    Ok(())
+}
+
+fn render(handoff: &Handoff) -> String {
+    let mut out = format!(
+        "Handoff for {} (written {}",
+        handoff.branch,
+        handoff
+            .written_at
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+    );
+    if let Some(agent) = &handoff.agent {
+        out.push_str(&format!(" by {}", agent.agent));
+    }
+    if let Some(commit) = &handoff.commit {
+        out.push_str(&format!(" at {}", commit));
+    }
+    out.push_str(")\n");
+
+    if let (Some(then), Some(now)) = (&handoff.commit, git(&["rev-parse", "--short", "HEAD"])) {
+        if *then != now && handoff.branch == current_branch().unwrap_or_default() {
+            out.push_str(&format!(
+                "Note: the branch has moved on since ({} -> {})\n",
+                then, now
+            ));
+        }
+    }
+
+    if let Some(goal) = &handoff.goal {
+        out.push_str(&format!("\nGoal: {}\n", goal));
+    }
+    if !handoff.tasks.is_empty() {
+        out.push_str(&format!("\nIn progress: {}\n", handoff.tasks.join(", ")));
+    }
+    if !handoff.questions.is_empty() {
+        out.push_str("\nOpen questions:\n");
+        for question in &handoff.questions {
+            out.push_str(&format!("- {}\n", question));
+        }
+    }
+    if !handoff.next_steps.is_empty() {
+        out.push_str("\nNext steps:\n");
+        for (i, step) in handoff.next_steps.iter().enumerate() {
+            out.push_str(&format!("{}. {}\n", i + 1, step));
+        }
+    }
+    if !handoff.touched_files.is_empty() {
+        out.push_str("\nUncommitted changes at the time:\n");
+        for file in &handoff.touched_files {
+            out.push_str(&format!("  {}\n", file));
+        }
+    }
+    out
+}
+
+fn current_branch() -> Result<String> {
+    let branch = git(&["rev-parse", "--abbrev-ref", "HEAD"])
+        .context("not in a git repository (handoffs are kept per branch)")?;
+    if branch == "HEAD" {
+        // Detached: key the handoff by commit instead
+        return git(&["rev-parse", "--short", "HEAD"]).context("could not determine HEAD");
+    }
+    Ok(branch)
+}
+
+/// Handoffs live in the state dir, keyed by the repo's store name so they
+/// follow the same naming as its memories.
+fn handoff_path(branch: &str) -> Result<PathBuf> {
+    let state = dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .context("could not determine state directory")?;
+    let repo = memory::store_name()?.unwrap_or_else(|| "default".to_string());
+    Ok(state
+        .join("agntz")
+        .join("handoff")
+        .join(repo)
+        .join(format!("{}.json", branch.replace('/', "%2F"))))
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string(),
+    )
+}
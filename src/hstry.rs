@@ -0,0 +1,166 @@
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::process::Command;
+
+use crate::agent;
+
+#[derive(Deserialize)]
+struct HstryJsonResponse<T> {
+    ok: bool,
+    result: Option<T>,
+    error: Option<String>,
+}
+
+/// One message of a stored conversation.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Message {
+    #[serde(alias = "message_idx")]
+    pub idx: i32,
+    pub role: String,
+    pub content: String,
+    #[serde(default)]
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Run an hstry command with `--json` and unwrap its response envelope.
+pub fn run_json<T: DeserializeOwned>(args: &[String]) -> Result<Option<T>> {
+    let mut cmd = Command::new("hstry");
+    // Reads change nothing, so there is nothing to log if hstry ignores this
+    agent::attribute("hstry", &mut cmd);
+    let output = cmd
+        .args(args)
+        .arg("--json")
+        .output()
+        .context("failed to run hstry - is hstry installed and the service running?")?;
+
+    let command = args.first().map(String::as_str).unwrap_or("hstry");
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("hstry {command} failed: {stderr}");
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let response: HstryJsonResponse<T> = serde_json::from_str(&stdout)
+        .with_context(|| format!("failed to parse hstry {command} output"))?;
+
+    if !response.ok {
+        let error = response
+            .error
+            .unwrap_or_else(|| format!("hstry {command} failed"));
+        anyhow::bail!(error);
+    }
+    Ok(response.result)
+}
+
+/// The flags an hstry command accepts, going by its `--help`; older hstry
+/// versions lack some filters.
+pub fn flags(command: &str) -> HashSet<String> {
+    let Ok(output) = Command::new("hstry").args([command, "--help"]).output() else {
+        return HashSet::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '=')
+        .filter(|word| word.starts_with("--"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// A stored conversation. Listings leave `messages` empty.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Conversation {
+    #[serde(default, alias = "conversation_id")]
+    pub id: String,
+    #[serde(default)]
+    pub external_id: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub workspace: Option<String>,
+    #[serde(default)]
+    pub source_id: Option<String>,
+    #[serde(default)]
+    pub source_adapter: Option<String>,
+    #[serde(default)]
+    pub source_path: Option<String>,
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub messages: Vec<Message>,
+}
+
+impl Conversation {
+    pub fn matches(&self, id: &str) -> bool {
+        matches_id(
+            id,
+            &self.id,
+            self.external_id.as_deref(),
+            self.source_path.as_deref(),
+        )
+    }
+}
+
+/// Whether `id` names a conversation: its hstry ID, the agent's own session
+/// ID, or part of the path it was imported from.
+pub fn matches_id(
+    id: &str,
+    conversation_id: &str,
+    external_id: Option<&str>,
+    source_path: Option<&str>,
+) -> bool {
+    external_id == Some(id)
+        || conversation_id == id
+        || source_path.map(|path| path.contains(id)).unwrap_or(false)
+}
+
+/// A conversation with its messages, by session or conversation ID.
+pub fn conversation(id: &str) -> Result<Conversation> {
+    // hstry returns the conversation with its messages, or just the messages
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Shown {
+        Messages(Vec<Message>),
+        Conversation(Box<Conversation>),
+    }
+
+    let shown = run_json(&["show".to_string(), id.to_string()])?
+        .with_context(|| format!("no conversation {} in hstry", id))?;
+    let mut conversation = match shown {
+        Shown::Conversation(conversation) => *conversation,
+        Shown::Messages(messages) => Conversation {
+            id: id.to_string(),
+            messages,
+            ..Default::default()
+        },
+    };
+    if conversation.id.is_empty() {
+        conversation.id = id.to_string();
+    }
+    conversation.messages.sort_by_key(|m| m.idx);
+    Ok(conversation)
+}
+
+/// Like [`conversation`], but also finds conversations by part of their
+/// source path, the way `--session` matches search hits.
+pub fn find(id: &str) -> Result<Conversation> {
+    let err = match conversation(id) {
+        Ok(conversation) => return Ok(conversation),
+        Err(err) => err,
+    };
+    let listed: Vec<Conversation> = run_json(&["list".to_string()])
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    match listed.iter().find(|c| c.matches(id)) {
+        Some(found) => conversation(&found.id),
+        None => Err(err),
+    }
+}
+
+/// All messages of a conversation, by session or conversation ID.
+pub fn messages(id: &str) -> Result<Vec<Message>> {
+    Ok(conversation(id)?.messages)
+}
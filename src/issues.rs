@@ -1,7 +1,13 @@
 use anyhow::{Context, Result};
 use clap::Subcommand;
+use serde::{Deserialize, Serialize};
 use std::process::Command;
 
+use crate::{activity, agent};
+
+/// trx subcommands that change tasks, and so need attributing.
+const TRX_WRITES: &[&str] = &["create", "update", "close", "comment"];
+
 #[derive(Subcommand)]
 pub enum IssuesCommand {
     /// List all issues
@@ -132,12 +138,63 @@ pub async fn handle(command: Option<IssuesCommand>) -> Result<()> {
     }
 }
 
+/// A task as listed by `trx list --json`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Task {
+    pub id: String,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+}
+
+/// All tasks trx knows about.
+pub fn list_tasks() -> Result<Vec<Task>> {
+    let mut cmd = Command::new("trx");
+    agent::attribute("trx", &mut cmd);
+    let output = cmd
+        .args(["list", "--json"])
+        .output()
+        .context("failed to run trx - is trx installed?")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "trx list failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    // A bare list, or one wrapped in an object
+    let mut value: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("failed to parse trx list output")?;
+    for key in ["issues", "tasks", "result"] {
+        if let Some(list) = value.get_mut(key) {
+            value = list.take();
+            break;
+        }
+    }
+    serde_json::from_value(value).context("unexpected trx list output")
+}
+
+/// Add a comment to a task.
+pub fn comment(id: &str, text: &str) -> Result<()> {
+    run_trx(&["comment", id, text])
+}
+
 fn run_trx(args: &[&str]) -> Result<()> {
-    let output = Command::new("trx")
+    let mut cmd = Command::new("trx");
+    let unrecorded = agent::attribute("trx", &mut cmd);
+    let output = cmd
         .args(args)
         .output()
         .context("failed to run trx - is trx installed?")?;
 
+    if let Some(attribution) = unrecorded {
+        if args.first().is_some_and(|a| TRX_WRITES.contains(a)) {
+            activity::record_backend("trx", args, attribution, output.status.success());
+        }
+    }
+
     print!("{}", String::from_utf8_lossy(&output.stdout));
     if !output.stderr.is_empty() {
         eprint!("{}", String::from_utf8_lossy(&output.stderr));
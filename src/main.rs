@@ -1,17 +1,31 @@
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
-
+use std::time::Instant;
+
+mod activity;
+mod agent;
+mod config;
+mod find;
+mod handoff;
+mod hstry;
 mod issues;
 mod memory;
 mod schedule;
+mod search;
+mod session;
+mod text;
+mod time;
 mod tools;
 
+use handoff::HandoffCommand;
 use issues::IssuesCommand;
 use memory::MemoryCommand;
 use schedule::ScheduleCommand;
+use search::{SearchArgs, SearchCommand};
+use session::SessionCommand;
 use tools::ToolsCommand;
 
 #[derive(Parser)]
@@ -19,6 +33,10 @@ use tools::ToolsCommand;
 #[command(about = "Agent utility toolkit for AI coding agents")]
 #[command(version)]
 struct Cli {
+    /// mmry store to use (defaults to one derived from the current repo)
+    #[arg(long, global = true)]
+    store: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -42,34 +60,25 @@ enum Commands {
     Ready,
 
     /// Search agent session history
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Search {
+        #[command(subcommand)]
+        command: Option<SearchCommand>,
+        #[command(flatten)]
+        args: Box<SearchArgs>,
+    },
+
+    /// Search memories, tasks and session history at once
+    Find {
         /// Search query
         query: String,
-        /// Limit to specific workspace path (defaults to current repo/dir)
-        #[arg(short, long, alias = "repo")]
-        workspace: Option<String>,
-        /// Limit to last N days
-        #[arg(long)]
-        days: Option<u32>,
-        /// Limit to a specific session/conversation ID
-        #[arg(long)]
-        session: Option<String>,
-        /// Maximum results to return
+        /// Maximum results
         #[arg(short, long, default_value = "20")]
         limit: usize,
-        /// Search all workspaces (disables default workspace filter)
+        /// Search session history in every workspace, not just this one
         #[arg(long)]
         all_workspaces: bool,
-        /// Include tool calls/results
-        #[arg(long)]
-        include_tools: bool,
-        /// Include system context (AGENTS.md, etc.)
-        #[arg(long)]
-        include_system: bool,
-        /// Disable result deduplication
-        #[arg(long)]
-        no_dedup: bool,
-        /// Output raw JSON results
+        /// Output as JSON
         #[arg(long)]
         json: bool,
     },
@@ -92,9 +101,44 @@ enum Commands {
         shell: clap_complete::Shell,
     },
 
+    /// Agent session reports
+    Session {
+        #[command(subcommand)]
+        command: SessionCommand,
+    },
+
+    /// Notes for the next agent session on this branch
+    Handoff {
+        #[command(subcommand)]
+        command: HandoffCommand,
+    },
+
+    /// Show what agntz did, from the local activity log
+    Log {
+        /// Only entries after this time (2026-03-01, 7d, 12h, ...)
+        #[arg(long, value_parser = time::parse_time)]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        /// Only entries by this agent harness
+        #[arg(long)]
+        agent: Option<String>,
+        /// Maximum entries to show
+        #[arg(short, long, default_value = "50")]
+        limit: usize,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the detected agent identity and what it was based on
+    Whoami {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Initialize agntz for current repo (mmry store, AGENTS.md)
     Init {
-        /// Force re-initialization
+        /// Force re-initialization, and fully sync .memories/export.json
         #[arg(long)]
         force: bool,
     },
@@ -102,41 +146,59 @@ enum Commands {
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let started = Instant::now();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    let command = subcommand_path(&matches);
+
+    let result = run(cli).await;
+
+    // Reading the log shouldn't add to it
+    if command != "log" {
+        activity::record_invocation(&command, started.elapsed(), &result);
+    }
+    result
+}
+
+/// The subcommands that were run, e.g. `memory add`.
+fn subcommand_path(matches: &clap::ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut current = matches;
+    while let Some((name, sub)) = current.subcommand() {
+        names.push(name);
+        current = sub;
+    }
+    names.join(" ")
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    if let Some(store) = cli.store {
+        memory::set_store(store);
+    }
 
     match cli.command {
         Commands::Memory { command } => memory::handle(command).await,
         Commands::Tasks { command } => issues::handle(command).await,
         Commands::Ready => handle_ready().await,
-        Commands::Search {
+        Commands::Search { command, args } => search::handle(command, args).await,
+        Commands::Find {
             query,
-            workspace,
-            days,
-            session,
             limit,
             all_workspaces,
-            include_tools,
-            include_system,
-            no_dedup,
             json,
-        } => {
-            handle_search(
-                query,
-                workspace,
-                days,
-                session,
-                limit,
-                all_workspaces,
-                include_tools,
-                include_system,
-                no_dedup,
-                json,
-            )
-            .await
-        }
+        } => find::run(query, limit, all_workspaces, json).await,
         Commands::Tools { command } => tools::handle(command).await,
         Commands::Schedule { command } => schedule::handle(command).await,
         Commands::Completions { shell } => handle_completions(shell),
+        Commands::Session { command } => session::handle(command).await,
+        Commands::Handoff { command } => handoff::handle(command).await,
+        Commands::Log {
+            since,
+            agent,
+            limit,
+            json,
+        } => activity::handle_log(since, agent, limit, json),
+        Commands::Whoami { json } => agent::handle_whoami(json),
         Commands::Init { force } => handle_init(force).await,
     }
 }
@@ -155,224 +217,6 @@ async fn handle_ready() -> Result<()> {
     Ok(())
 }
 
-#[derive(serde::Deserialize)]
-struct HstryJsonResponse<T> {
-    ok: bool,
-    result: Option<T>,
-    error: Option<String>,
-}
-
-#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
-struct HstrySearchHit {
-    message_id: String,
-    conversation_id: String,
-    message_idx: i32,
-    role: String,
-    content: String,
-    snippet: String,
-    created_at: Option<chrono::DateTime<chrono::Utc>>,
-    conv_created_at: chrono::DateTime<chrono::Utc>,
-    conv_updated_at: Option<chrono::DateTime<chrono::Utc>>,
-    score: f32,
-    source_id: String,
-    external_id: Option<String>,
-    title: Option<String>,
-    workspace: Option<String>,
-    source_adapter: String,
-    source_path: Option<String>,
-    host: Option<String>,
-}
-
-async fn handle_search(
-    query: String,
-    workspace: Option<String>,
-    days: Option<u32>,
-    session: Option<String>,
-    limit: usize,
-    all_workspaces: bool,
-    include_tools: bool,
-    include_system: bool,
-    no_dedup: bool,
-    json: bool,
-) -> Result<()> {
-    let mut args = vec!["search".to_string(), query.clone(), "--json".to_string()];
-
-    let workspace_filter = if all_workspaces {
-        None
-    } else {
-        workspace.or_else(resolve_default_workspace)
-    };
-
-    if let Some(workspace) = workspace_filter.as_ref() {
-        args.push("--workspace".to_string());
-        args.push(workspace.clone());
-    }
-
-    let dedup = !no_dedup;
-    if dedup {
-        args.push("--dedup".to_string());
-    }
-    if !include_tools {
-        args.push("--no-tools".to_string());
-    }
-    if include_system {
-        args.push("--include-system".to_string());
-    }
-
-    let fetch_limit = if session.is_some() {
-        (limit.saturating_mul(10)).clamp(limit.max(20), 1000)
-    } else {
-        limit
-    };
-    args.push("--limit".to_string());
-    args.push(fetch_limit.to_string());
-
-    let output = Command::new("hstry")
-        .args(&args)
-        .output()
-        .context("failed to run hstry - is hstry installed and the service running?")?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("hstry search failed: {stderr}");
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let response: HstryJsonResponse<Vec<HstrySearchHit>> =
-        serde_json::from_str(&stdout).context("failed to parse hstry search output")?;
-
-    if !response.ok {
-        let error = response
-            .error
-            .unwrap_or_else(|| "hstry search failed".to_string());
-        anyhow::bail!(error);
-    }
-
-    let mut hits = response.result.unwrap_or_default();
-    hits = filter_hits(hits, session.as_deref(), days);
-    hits.truncate(limit);
-
-    if json {
-        let payload = serde_json::json!({ "hits": hits });
-        println!("{}", serde_json::to_string_pretty(&payload)?);
-        return Ok(());
-    }
-
-    print_compact_hits(&hits);
-    Ok(())
-}
-
-fn resolve_default_workspace() -> Option<String> {
-    let output = Command::new("git")
-        .args(["rev-parse", "--show-toplevel"])
-        .output()
-        .ok()?;
-
-    if output.status.success() {
-        let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        if !path.is_empty() {
-            return Some(path);
-        }
-    }
-
-    std::env::current_dir()
-        .ok()
-        .map(|dir| dir.to_string_lossy().to_string())
-}
-
-fn filter_hits(
-    hits: Vec<HstrySearchHit>,
-    session: Option<&str>,
-    days: Option<u32>,
-) -> Vec<HstrySearchHit> {
-    let mut filtered = Vec::new();
-    let cutoff = days.map(|d| chrono::Utc::now() - chrono::Duration::days(i64::from(d)));
-
-    for hit in hits {
-        if let Some(session_id) = session {
-            let session_match = hit
-                .external_id
-                .as_deref()
-                .map(|id| id == session_id)
-                .unwrap_or(false)
-                || hit.conversation_id == session_id
-                || hit
-                    .source_path
-                    .as_deref()
-                    .map(|path| path.contains(session_id))
-                    .unwrap_or(false);
-            if !session_match {
-                continue;
-            }
-        }
-
-        if let Some(cutoff) = cutoff {
-            let timestamp = hit
-                .created_at
-                .or(hit.conv_updated_at)
-                .unwrap_or(hit.conv_created_at);
-            if timestamp < cutoff {
-                continue;
-            }
-        }
-
-        filtered.push(hit);
-    }
-
-    filtered
-}
-
-fn print_compact_hits(hits: &[HstrySearchHit]) {
-    if hits.is_empty() {
-        println!("No results found.");
-        return;
-    }
-
-    for hit in hits {
-        let session_id = hit
-            .external_id
-            .as_deref()
-            .unwrap_or(hit.conversation_id.as_str());
-        let title = compact_label(hit.title.as_deref().unwrap_or("Untitled"), 40);
-        let snippet = compact_snippet(&hit.snippet, 160);
-        let workspace = hit
-            .workspace
-            .as_deref()
-            .and_then(|w| w.split('/').last())
-            .unwrap_or("-");
-
-        println!(
-            "{score:>5.2} {source} {role} {session} #{idx} {workspace} {title} - {snippet}",
-            score = hit.score,
-            source = hit.source_id,
-            role = hit.role,
-            session = session_id,
-            idx = hit.message_idx,
-            workspace = workspace,
-            title = title
-        );
-    }
-}
-
-fn compact_snippet(snippet: &str, max_len: usize) -> String {
-    let mut collapsed = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
-    if collapsed.len() > max_len {
-        collapsed.truncate(max_len.saturating_sub(3));
-        collapsed.push_str("...");
-    }
-    collapsed
-}
-
-fn compact_label(value: &str, max_len: usize) -> String {
-    if value.len() <= max_len {
-        return value.to_string();
-    }
-    let mut trimmed = value.to_string();
-    trimmed.truncate(max_len.saturating_sub(3));
-    trimmed.push_str("...");
-    trimmed
-}
-
 fn handle_completions(shell: clap_complete::Shell) -> Result<()> {
     use clap::CommandFactory;
     use clap_complete::generate;
@@ -410,11 +254,12 @@ fn get_repo_name() -> Option<String> {
 
 async fn handle_init(force: bool) -> Result<()> {
     let repo_name = get_repo_name().context("could not determine repo name")?;
+    let store = memory::store_name()?.unwrap_or_else(|| repo_name.clone());
     println!("Initializing agntz for repo: {}", repo_name);
 
     // 1. Initialize mmry with repo-specific store
-    println!("\n[1/3] Initializing mmry store...");
-    let mut mmry_args = vec!["init", "--store", &repo_name];
+    println!("\n[1/4] Initializing mmry store '{}'...", store);
+    let mut mmry_args = vec!["init", "--store", &store];
     if force {
         mmry_args.push("--force");
     }
@@ -431,7 +276,7 @@ async fn handle_init(force: bool) -> Result<()> {
     }
 
     // 2. Initialize trx
-    println!("[2/3] Initializing trx...");
+    println!("[2/4] Initializing trx...");
     let trx_args = vec!["init", "--prefix", &repo_name];
     let trx_output = Command::new("trx")
         .args(&trx_args)
@@ -446,10 +291,9 @@ async fn handle_init(force: bool) -> Result<()> {
     }
 
     // 3. Append to AGENTS.md
-    println!("[3/3] Updating AGENTS.md...");
+    println!("[3/4] Updating AGENTS.md...");
     let agents_md = PathBuf::from("AGENTS.md");
-    let agntz_section = format!(
-        r#"
+    let agntz_section = r#"
 ## agntz
 
 Use agntz for memory:
@@ -458,9 +302,16 @@ Use agntz for memory:
 agntz memory search "topic"    # Find relevant context
 agntz memory add "insight" -c category
 agntz memory list
+agntz memory sync              # Share memories via .memories/export.json
+```
+
+Pick up where the last session left off, and leave notes for the next one:
+
+```bash
+agntz handoff read
+agntz handoff write --goal "..." --task <id> --question "..." --next "..."
 ```
-"#
-    );
+"#;
 
     if agents_md.exists() {
         let content = fs::read_to_string(&agents_md)?;
@@ -491,6 +342,12 @@ agntz memory list
         println!("  Created AGENTS.md with agntz section");
     }
 
+    // 4. Pull in memories shared through the repo
+    println!("[4/4] Syncing shared memories...");
+    if !memory::sync_shared(force).await? {
+        println!("  No .memories/export.json found, skipping");
+    }
+
     println!("\nDone! agntz initialized for '{}'", repo_name);
     Ok(())
 }
@@ -515,3 +372,33 @@ fn remove_agntz_section(content: &str) -> String {
 
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_search(args: &[&str]) -> (Option<SearchCommand>, Option<String>) {
+        let cli = Cli::try_parse_from(["agntz", "search"].iter().chain(args)).unwrap();
+        match cli.command {
+            Commands::Search { command, args } => (command, args.query.clone()),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn search_subcommands_take_precedence() {
+        let (command, _) = parse_search(&["show", "abc"]);
+        assert!(matches!(command, Some(SearchCommand::Show { id, .. }) if id == "abc"));
+    }
+
+    #[test]
+    fn search_query_after_double_dash_is_literal() {
+        let (command, query) = parse_search(&["--", "show"]);
+        assert!(command.is_none());
+        assert_eq!(query.as_deref(), Some("show"));
+
+        let (command, query) = parse_search(&["login", "--days", "3"]);
+        assert!(command.is_none());
+        assert_eq!(query.as_deref(), Some("login"));
+    }
+}
@@ -1,9 +1,39 @@
 use anyhow::{Context, Result};
 use clap::Subcommand;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
 use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+use crate::agent::Attribution;
+use crate::config::{Config, StoreNaming};
+
+mod audit;
+mod formats;
+mod kinds;
+mod links;
+mod review;
+mod safety;
+mod search;
+mod stores;
+mod sync;
+
+use audit::AuditOptions;
+use formats::MemoryFormat;
+use kinds::{KindFields, MemoryKind};
+use links::Links;
+use review::ReviewArgs;
+pub use safety::redact_secrets;
+pub use search::{search_memories, MemoryHit};
+use search::{MemoryFilters, SearchOptions};
+use stores::StoresCommand;
+use sync::{Prefer, SyncOptions};
+
+/// Store chosen with the global `--store` flag, overriding the derived name.
+static STORE_OVERRIDE: OnceLock<String> = OnceLock::new();
 
 #[derive(Subcommand)]
 pub enum MemoryCommand {
@@ -20,6 +50,13 @@ pub enum MemoryCommand {
         /// Importance (1-10)
         #[arg(short, long)]
         importance: Option<u8>,
+        #[command(flatten)]
+        kind: KindFields,
+        #[command(flatten)]
+        links: Links,
+        /// Store content even if it looks like it contains secrets or personal data
+        #[arg(long)]
+        allow_secrets: bool,
     },
 
     /// Search memories
@@ -35,16 +72,24 @@ pub enum MemoryCommand {
         /// Output as JSON
         #[arg(long)]
         json: bool,
+        /// Search these stores instead of the repo store (comma-separated)
+        #[arg(long, value_delimiter = ',', conflicts_with = "all_stores")]
+        stores: Vec<String>,
+        /// Search every store
+        #[arg(long)]
+        all_stores: bool,
+        #[command(flatten)]
+        filters: MemoryFilters,
     },
 
     /// Export memories
     Export {
-        /// Output file (defaults to .memories/export.json or .memories/export.md)
+        /// Output file or directory (defaults to .memories/export.<ext>, or .memories/obsidian/)
         #[arg(short, long)]
         output: Option<PathBuf>,
-        /// Output format: json, md
-        #[arg(short, long, default_value = "json")]
-        format: String,
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = MemoryFormat::Json)]
+        format: MemoryFormat,
         /// Export all stores
         #[arg(long)]
         all: bool,
@@ -52,15 +97,37 @@ pub enum MemoryCommand {
 
     /// Import memories
     Import {
-        /// Input file
+        /// Input file (or Obsidian vault directory)
+        file: PathBuf,
+        /// Input format (detected from the file when omitted)
+        #[arg(short, long, value_enum)]
+        format: Option<MemoryFormat>,
+    },
+
+    /// Reconcile the repo-committed export with the store
+    Sync {
+        /// Shared memory file
+        #[arg(long, default_value = sync::SHARED_EXPORT)]
         file: PathBuf,
+        /// Only report differences; exit non-zero if file and store diverge (for CI)
+        #[arg(long)]
+        check: bool,
+        /// Resolve conflicting edits in favour of the file or the store
+        #[arg(long, value_enum)]
+        prefer: Option<Prefer>,
+        /// Show what would change without applying it
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Show memory statistics
     Stats,
 
-    /// List available stores
-    Stores,
+    /// List and manage stores
+    Stores {
+        #[command(subcommand)]
+        command: Option<StoresCommand>,
+    },
 
     /// List memories
     List {
@@ -70,6 +137,9 @@ pub enum MemoryCommand {
         /// Filter by category
         #[arg(short, long)]
         category: Option<String>,
+        /// Filter by kind
+        #[arg(short, long, value_enum)]
+        kind: Option<MemoryKind>,
         /// Output as JSON
         #[arg(long)]
         json: bool,
@@ -78,6 +148,39 @@ pub enum MemoryCommand {
         full: bool,
     },
 
+    /// Show memories attached to a file, or to files under a directory
+    For {
+        /// File or directory
+        path: PathBuf,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check file and symbol references against git for stale memories
+    Audit {
+        /// Fraction of a file's lines changed since the memory was added
+        /// before it counts as heavily modified
+        #[arg(long, default_value_t = 0.5)]
+        threshold: f64,
+        /// Point renamed references at the new path; tag the rest `stale`
+        /// and lower their importance
+        #[arg(long)]
+        fix: bool,
+        /// How much --fix lowers the importance of stale memories
+        #[arg(long, default_value_t = 2)]
+        downgrade: u8,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Accept, edit or reject memories from agents waiting for review
+    Review {
+        #[command(flatten)]
+        args: ReviewArgs,
+    },
+
     /// Remove a memory
     #[command(alias = "rm")]
     Remove {
@@ -93,27 +196,87 @@ pub async fn handle(command: MemoryCommand) -> Result<()> {
             category,
             tags,
             importance,
-        } => handle_add(content, category, tags, importance).await,
+            kind,
+            links,
+            allow_secrets,
+        } => {
+            handle_add(
+                content,
+                category,
+                tags,
+                importance,
+                kind,
+                links,
+                allow_secrets,
+            )
+            .await
+        }
         MemoryCommand::Search {
             query,
             mode,
             limit,
             json,
-        } => handle_search(query, mode, limit, json).await,
+            stores,
+            all_stores,
+            filters,
+        } => {
+            search::run(SearchOptions {
+                query,
+                mode,
+                limit,
+                json,
+                stores,
+                all_stores,
+                filters,
+            })
+            .await
+        }
         MemoryCommand::Export {
             output,
             format,
             all,
         } => handle_export(output, format, all).await,
-        MemoryCommand::Import { file } => handle_import(file).await,
+        MemoryCommand::Import { file, format } => handle_import(file, format).await,
+        MemoryCommand::Sync {
+            file,
+            check,
+            prefer,
+            dry_run,
+        } => {
+            sync::run(SyncOptions {
+                file,
+                check,
+                prefer,
+                dry_run,
+                pull_only: false,
+            })
+            .await
+        }
         MemoryCommand::Stats => handle_stats().await,
-        MemoryCommand::Stores => handle_stores().await,
+        MemoryCommand::Stores { command } => stores::handle(command).await,
         MemoryCommand::List {
             limit,
             category,
+            kind,
             json,
             full,
-        } => handle_list(limit, category, json, full).await,
+        } => handle_list(limit, category, kind, json, full).await,
+        MemoryCommand::For { path, json } => links::handle_for(path, json).await,
+        MemoryCommand::Audit {
+            threshold,
+            fix,
+            downgrade,
+            json,
+        } => {
+            audit::run(AuditOptions {
+                threshold,
+                fix,
+                downgrade,
+                json,
+            })
+            .await
+        }
+        MemoryCommand::Review { args } => review::run(args).await,
         MemoryCommand::Remove { id } => handle_remove(id).await,
     }
 }
@@ -123,6 +286,9 @@ async fn handle_add(
     category: Option<String>,
     tags: Option<String>,
     importance: Option<u8>,
+    kind: KindFields,
+    links: Links,
+    allow_secrets: bool,
 ) -> Result<()> {
     let mut args = vec!["add".to_string()];
 
@@ -136,6 +302,39 @@ async fn handle_add(
         content
     };
 
+    let (actual_content, kind_tag) = kinds::compose(actual_content, &kind)?;
+    let extra_tags: Vec<String> = kind_tag
+        .into_iter()
+        .chain(links::resolve(&links)?)
+        .collect();
+    let tags = match (tags, extra_tags.is_empty()) {
+        (t, true) => t,
+        (Some(t), false) => Some(format!("{},{}", t, extra_tags.join(","))),
+        (None, false) => Some(extra_tags.join(",")),
+    };
+
+    // Memories are long-lived and shared, so keep credentials out of them
+    let config = Config::load()?;
+    let (actual_content, category, tags) = if allow_secrets {
+        (actual_content, category, tags)
+    } else {
+        let policy = &config.memory.safety;
+        (
+            safety::enforce(actual_content, policy)?,
+            category
+                .map(|c| safety::enforce_field("category", c, policy))
+                .transpose()?,
+            tags.map(|t| safety::enforce_field("tags", t, policy))
+                .transpose()?,
+        )
+    };
+
+    if config.memory.review {
+        if let Some(attribution) = Attribution::detect() {
+            return review::enqueue(actual_content, category, tags, importance, attribution);
+        }
+    }
+
     args.push(actual_content);
 
     if let Some(cat) = category {
@@ -156,75 +355,130 @@ async fn handle_add(
     run_mmry(&args)
 }
 
-async fn handle_search(query: String, mode: String, limit: usize, json: bool) -> Result<()> {
-    let mut args = vec![
-        "search".to_string(),
-        query,
-        "--mode".to_string(),
-        mode,
-        "--limit".to_string(),
-        limit.to_string(),
-    ];
-
-    if json {
-        args.push("--json".to_string());
-    }
-
-    run_mmry(&args)
-}
-
-async fn handle_export(output: Option<PathBuf>, format: String, all: bool) -> Result<()> {
+async fn handle_export(output: Option<PathBuf>, format: MemoryFormat, all: bool) -> Result<()> {
     // Determine output path
     let output_path = match output {
         Some(p) => p,
         None => {
             // Default to .memories/ directory
-            let memories_dir = PathBuf::from(".memories");
-            fs::create_dir_all(&memories_dir)?;
-
-            let filename = match format.as_str() {
-                "md" | "markdown" => "export.md",
-                _ => "export.json",
-            };
-            memories_dir.join(filename)
+            fs::create_dir_all(".memories")?;
+            format.default_export_path()
         }
     };
 
-    match format.as_str() {
-        "md" | "markdown" => export_markdown(&output_path, all).await,
-        _ => export_json(&output_path, all).await,
-    }
+    // Parsed even for JSON, so memories pending review stay out of the file
+    let memories = fetch_memories(all)?;
+    formats::write(format, &memories, &output_path)?;
+    println!(
+        "Exported {} memories to {}",
+        memories.len(),
+        output_path.display()
+    );
+    Ok(())
 }
 
-async fn export_json(output: &PathBuf, all: bool) -> Result<()> {
-    let mut args = vec![
-        "export".to_string(),
-        "-o".to_string(),
-        output.to_string_lossy().to_string(),
-    ];
+/// A memory as it appears in mmry's JSON export.
+///
+/// Fields agntz doesn't know about are kept in `extra` so they survive a
+/// round-trip through the lossless formats.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Memory {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_tags",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub importance: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
 
-    if all {
-        args.push("--all".to_string());
+impl Memory {
+    /// Name of the agent that stored the memory, as recorded by mmry.
+    pub fn agent(&self) -> Option<&str> {
+        match self.extra.get("agent")? {
+            serde_json::Value::String(name) => Some(name),
+            serde_json::Value::Object(agent) => agent.get("name")?.as_str(),
+            _ => None,
+        }
     }
 
-    run_mmry(&args)?;
-    println!("Exported to {}", output.display());
-    Ok(())
+    /// Session recorded in the agent metadata (`MMRY_AGENT_META`).
+    pub fn session(&self) -> Option<&str> {
+        self.agent_meta("session")
+    }
+
+    /// Whether the memory was added in `session`, given by name or ID.
+    pub fn in_session(&self, session: &str) -> bool {
+        self.session() == Some(session) || self.agent_meta("session_id") == Some(session)
+    }
+
+    fn agent_meta(&self, key: &str) -> Option<&str> {
+        ["agent_meta", "metadata", "meta"]
+            .iter()
+            .filter_map(|k| self.extra.get(*k))
+            .chain(self.extra.get("agent"))
+            .find_map(|meta| meta.get(key).and_then(|s| s.as_str()))
+    }
+
+    pub fn created(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.created_at
+            .as_deref()
+            .and_then(crate::time::parse_timestamp)
+    }
 }
 
-#[derive(Deserialize)]
-struct Memory {
-    content: String,
-    category: Option<String>,
-    importance: Option<u8>,
-    #[serde(default)]
-    #[allow(dead_code)]
-    created_at: Option<String>,
+/// Accept tags either as a list or as a comma-separated string.
+fn deserialize_tags<'de, D>(deserializer: D) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Tags {
+        List(Vec<String>),
+        Joined(String),
+    }
+
+    Ok(match Option::<Tags>::deserialize(deserializer)? {
+        Some(Tags::List(tags)) => tags,
+        Some(Tags::Joined(joined)) => formats::split_tags(&joined),
+        None => Vec::new(),
+    })
+}
+
+/// A temp file path unique to this run, so concurrent runs don't clobber
+/// each other's files.
+fn temp_path(name: &str) -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    std::env::temp_dir().join(format!(
+        "agntz-{}-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed),
+        name
+    ))
 }
 
-async fn export_markdown(output: &PathBuf, all: bool) -> Result<()> {
-    // First export to JSON, then convert
-    let temp_json = std::env::temp_dir().join("agnt_export_temp.json");
+/// Export the current store via mmry and parse the result, leaving out
+/// memories still pending review.
+pub fn fetch_memories(all: bool) -> Result<Vec<Memory>> {
+    let mut memories = fetch_with_pending(all)?;
+    memories.retain(|m| !m.is_pending());
+    Ok(memories)
+}
+
+/// Like [`fetch_memories`], including memories pending review.
+fn fetch_with_pending(all: bool) -> Result<Vec<Memory>> {
+    let temp_json = temp_path("export.json");
 
     let mut args = vec![
         "export".to_string(),
@@ -236,73 +490,123 @@ async fn export_markdown(output: &PathBuf, all: bool) -> Result<()> {
         args.push("--all".to_string());
     }
 
-    run_mmry(&args)?;
+    run_mmry_quiet(&args)?;
 
-    // Read and convert to markdown
     let json_content = fs::read_to_string(&temp_json)?;
-    let memories: Vec<Memory> = serde_json::from_str(&json_content).unwrap_or_default();
-
-    let mut md = String::new();
-    md.push_str("# Memories\n\n");
-
-    // Group by category
-    let mut by_category: std::collections::HashMap<String, Vec<&Memory>> =
-        std::collections::HashMap::new();
-    for mem in &memories {
-        let cat = mem
-            .category
-            .clone()
-            .unwrap_or_else(|| "uncategorized".to_string());
-        by_category.entry(cat).or_default().push(mem);
-    }
-
-    for (category, mems) in by_category {
-        md.push_str(&format!("## {}\n\n", category));
-        for mem in mems {
-            let importance = mem
-                .importance
-                .map(|i| format!(" [i:{}]", i))
-                .unwrap_or_default();
-            md.push_str(&format!("- {}{}\n", mem.content.trim(), importance));
-        }
-        md.push('\n');
-    }
+    fs::remove_file(&temp_json).ok();
+    formats::parse_json(&json_content)
+}
 
-    fs::write(output, &md)?;
+/// Import memories into the current store without printing mmry's output.
+fn import_memories(memories: &[Memory]) -> Result<()> {
+    let temp_json = temp_path("import.json");
+    fs::write(&temp_json, serde_json::to_string_pretty(memories)?)?;
+    let result = run_mmry_quiet(&[
+        "import".to_string(),
+        temp_json.to_string_lossy().to_string(),
+    ]);
     fs::remove_file(&temp_json).ok();
+    result.map(|_| ())
+}
 
-    println!(
-        "Exported {} memories to {}",
-        memories.len(),
-        output.display()
-    );
+/// Overwrite existing memories (matched by ID) with new versions, keeping
+/// their IDs so sync pairs and `task:`/`file:` references still hold.
+///
+/// mmry can't update a memory in place, so the old versions are removed and
+/// the new ones imported under the same IDs. They are saved to a file in the
+/// state directory first, so a failed import can't lose them.
+fn replace_memories(memories: &[Memory]) -> Result<()> {
+    let state = dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .context("could not determine state directory")?;
+    let backup = state.join("agntz").join("backup").join(format!(
+        "replace-{}.json",
+        chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f")
+    ));
+    if let Some(parent) = backup.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&backup, serde_json::to_string_pretty(memories)?)
+        .with_context(|| format!("failed to write {}", backup.display()))?;
+
+    let replaced = memories
+        .iter()
+        .filter_map(|memory| memory.id.as_ref())
+        .try_for_each(|id| run_mmry_quiet(&["rm".to_string(), id.clone()]).map(|_| ()))
+        .and_then(|_| import_memories(memories));
+    replaced.with_context(|| {
+        format!(
+            "failed to replace memories; they are saved in {} (agntz memory import <file>)",
+            backup.display()
+        )
+    })?;
+    fs::remove_file(&backup).ok();
     Ok(())
 }
 
-async fn handle_import(file: PathBuf) -> Result<()> {
-    let args = vec!["import".to_string(), file.to_string_lossy().to_string()];
+async fn handle_import(file: PathBuf, format: Option<MemoryFormat>) -> Result<()> {
+    let format = match format {
+        Some(f) => f,
+        None => formats::detect(&file)?,
+    };
 
-    run_mmry(&args)
+    // mmry reads its own JSON export directly
+    if format == MemoryFormat::Json {
+        let args = vec!["import".to_string(), file.to_string_lossy().to_string()];
+        return run_mmry(&args);
+    }
+
+    let memories = formats::read(format, &file)?;
+    if memories.is_empty() {
+        println!("No memories found in {}", file.display());
+        return Ok(());
+    }
+
+    let temp_json = temp_path("import.json");
+    fs::write(&temp_json, serde_json::to_string_pretty(&memories)?)?;
+    let result = run_mmry(&[
+        "import".to_string(),
+        temp_json.to_string_lossy().to_string(),
+    ]);
+    fs::remove_file(&temp_json).ok();
+    result
 }
 
-async fn handle_stats() -> Result<()> {
-    run_mmry(&["stats".to_string()])
+/// Pull the repo's committed memories into the store, if the repo has any.
+/// The committed file is only rewritten with `full`.
+pub async fn sync_shared(full: bool) -> Result<bool> {
+    let file = PathBuf::from(sync::SHARED_EXPORT);
+    if !file.exists() {
+        return Ok(false);
+    }
+    sync::run(SyncOptions {
+        file,
+        check: false,
+        prefer: None,
+        dry_run: false,
+        pull_only: !full,
+    })
+    .await?;
+    Ok(true)
 }
 
-async fn handle_stores() -> Result<()> {
-    // Don't use auto-store for listing stores
-    run_mmry_raw(&["stores", "list"])
+async fn handle_stats() -> Result<()> {
+    run_mmry(&["stats".to_string()])
 }
 
 async fn handle_list(
     limit: Option<usize>,
     category: Option<String>,
+    kind: Option<MemoryKind>,
     json: bool,
     full: bool,
 ) -> Result<()> {
     let mut args = vec!["ls".to_string()];
 
-    if let Some(l) = limit {
+    // Kinds and memories pending review are filtered here, so mmry must not
+    // cut the list short
+    let filtered = kind.is_some() || review::enabled();
+    if let Some(l) = limit.filter(|_| !filtered) {
         args.push("--limit".to_string());
         args.push(l.to_string());
     }
@@ -312,15 +616,32 @@ async fn handle_list(
         args.push(cat);
     }
 
-    if json {
+    if json && !filtered {
         args.push("--json".to_string());
+        if full {
+            args.push("--full".to_string());
+        }
+        return run_mmry(&args);
     }
 
+    args.push("--json".to_string());
     if full {
         args.push("--full".to_string());
     }
+    let mut memories = formats::parse_json(&run_mmry_quiet(&args)?)?;
+    memories.retain(|m| !m.is_pending() && kind.is_none_or(|kind| m.kind() == Some(kind)));
+    if let Some(l) = limit {
+        memories.truncate(l);
+    }
 
-    run_mmry(&args)
+    if json {
+        println!("{}", serde_json::to_string_pretty(&memories)?);
+    } else if memories.is_empty() {
+        println!("No memories found.");
+    } else {
+        print!("{}", kinds::render_list(&memories));
+    }
+    Ok(())
 }
 
 async fn handle_remove(id: String) -> Result<()> {
@@ -346,8 +667,73 @@ fn run_mmry_raw(args: &[&str]) -> Result<()> {
     Ok(())
 }
 
+/// Use `store` for every mmry call instead of deriving it from the repo.
+pub fn set_store(store: String) {
+    STORE_OVERRIDE.set(store).ok();
+}
+
+/// The mmry store for the current repo: the `--store` override if given,
+/// otherwise derived from the repo according to `memory.store_naming`.
+pub fn store_name() -> Result<Option<String>> {
+    if let Some(store) = STORE_OVERRIDE.get() {
+        return Ok(Some(store.clone()));
+    }
+
+    let name = match Config::load()?.memory.store_naming {
+        StoreNaming::Basename => get_repo_name(),
+        StoreNaming::OwnerRepo => get_repo_owner_and_name()
+            .map(|(owner, repo)| format!("{}-{}", owner, repo))
+            .or_else(get_repo_name),
+        StoreNaming::PathHash => get_repo_name().map(|repo| {
+            let digest = sha2::Sha256::digest(repo_root().to_string_lossy().as_bytes());
+            let hash: String = digest
+                .iter()
+                .take(4)
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            format!("{}-{}", repo, hash)
+        }),
+    };
+    Ok(name)
+}
+
+/// Owner and repo name parsed from the origin URL.
+fn get_repo_owner_and_name() -> Option<(String, String)> {
+    let output = Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    // Handles git@host:owner/repo.git as well as https://host/owner/repo.git
+    let url = String::from_utf8_lossy(&output.stdout);
+    let mut parts = url
+        .trim()
+        .trim_end_matches(".git")
+        .rsplit(['/', ':'])
+        .filter(|s| !s.is_empty());
+    let repo = parts.next()?.to_string();
+    let owner = parts.next()?.to_string();
+    Some((owner, repo))
+}
+
+/// Top-level directory of the current git checkout, or the current directory.
+fn repo_root() -> PathBuf {
+    Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| PathBuf::from(String::from_utf8_lossy(&o.stdout).trim()))
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default()
+}
+
 /// Get the current repo name from git remote or directory name
-fn get_repo_name() -> Option<String> {
+pub fn get_repo_name() -> Option<String> {
     // Try to get repo name from git remote
     let output = Command::new("git")
         .args(["remote", "get-url", "origin"])
@@ -376,49 +762,19 @@ fn get_repo_name() -> Option<String> {
         .and_then(|p| p.file_name().map(|s| s.to_string_lossy().to_string()))
 }
 
-/// Agent identity detected from environment.
-struct AgentIdentity {
-    /// Harness name (e.g. "pi", "opencode")
-    harness: String,
-    /// Session ID or name if available
-    session: Option<String>,
-    /// Model string (e.g. "anthropic/claude-sonnet-4")
-    model: Option<String>,
-}
-
-/// Detect the agent harness from env vars.
-///
-/// Any harness (pi, opencode, aider, etc.) can set:
-///   AGENT_HARNESS=<name>          (e.g. "pi", "opencode")
-///   AGENT_SESSION_ID=<uuid>
-///   AGENT_SESSION_NAME=<readable> (set after auto-rename)
-///   AGENT_SESSION_FILE=<path>
-///   AGENT_MODEL=<provider>/<id>
-///   AGENT_CWD=<workdir>
-fn detect_agent() -> Option<AgentIdentity> {
-    let harness = std::env::var("AGENT_HARNESS").ok().filter(|s| !s.is_empty())?;
-
-    // Prefer session name (human-readable) over raw UUID
-    let session = std::env::var("AGENT_SESSION_NAME")
-        .ok()
-        .filter(|s| !s.is_empty())
-        .or_else(|| std::env::var("AGENT_SESSION_ID").ok().filter(|s| !s.is_empty()));
-    let model = std::env::var("AGENT_MODEL").ok().filter(|s| !s.is_empty());
-
-    Some(AgentIdentity {
-        harness,
-        session,
-        model,
-    })
+/// Build an mmry command for the current repo's store, attributed to the
+/// detected agent.
+fn mmry_command(args: &[String]) -> Result<Command> {
+    Ok(mmry_command_in(store_name()?.as_deref(), args))
 }
 
-fn run_mmry(args: &[String]) -> Result<()> {
+/// Build an mmry command for an explicit store, attributed to the detected agent.
+fn mmry_command_in(store: Option<&str>, args: &[String]) -> Command {
     let mut full_args = Vec::new();
 
-    // Auto-detect store from repo name
-    if let Some(repo) = get_repo_name() {
+    if let Some(store) = store {
         full_args.push("--store".to_string());
-        full_args.push(repo);
+        full_args.push(store.to_string());
     }
 
     full_args.extend(args.iter().cloned());
@@ -427,30 +783,13 @@ fn run_mmry(args: &[String]) -> Result<()> {
     cmd.args(&full_args);
 
     // Auto-identify the agent for memory attribution via env vars.
-    // mmry reads MMRY_AGENT, MMRY_AGENT_KIND, and MMRY_AGENT_META.
-    if let Some(identity) = detect_agent() {
-        cmd.env("MMRY_AGENT", &identity.harness);
-        cmd.env("MMRY_AGENT_KIND", "coding_agent");
-
-        // Build metadata with repo, session, and model context
-        let mut meta = serde_json::Map::new();
-        if let Some(repo) = get_repo_name() {
-            meta.insert("repo".to_string(), serde_json::Value::String(repo));
-        }
-        if let Some(ref session) = identity.session {
-            meta.insert("session".to_string(), serde_json::Value::String(session.clone()));
-        }
-        if let Some(ref model) = identity.model {
-            meta.insert("model".to_string(), serde_json::Value::String(model.clone()));
-        }
-        if !meta.is_empty() {
-            if let Ok(meta_json) = serde_json::to_string(&meta) {
-                cmd.env("MMRY_AGENT_META", meta_json);
-            }
-        }
-    }
+    crate::agent::attribute("mmry", &mut cmd);
 
-    let output = cmd
+    cmd
+}
+
+fn run_mmry(args: &[String]) -> Result<()> {
+    let output = mmry_command(args)?
         .output()
         .context("failed to run mmry - is mmry installed?")?;
 
@@ -465,3 +804,17 @@ fn run_mmry(args: &[String]) -> Result<()> {
 
     Ok(())
 }
+
+/// Run mmry and return its stdout instead of printing it.
+fn run_mmry_quiet(args: &[String]) -> Result<String> {
+    let output = mmry_command(args)?
+        .output()
+        .context("failed to run mmry - is mmry installed?")?;
+
+    if !output.status.success() {
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        anyhow::bail!("mmry command failed");
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
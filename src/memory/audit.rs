@@ -0,0 +1,333 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::links::{symbol_exists, FileRef};
+use super::{fetch_memories, replace_memories, repo_root, Memory};
+
+/// Tag added to memories whose references went stale.
+const STALE_TAG: &str = "stale";
+
+pub struct AuditOptions {
+    /// Fraction of a file's lines that must have changed to call it heavily modified
+    pub threshold: f64,
+    /// Update renamed paths, tag stale memories and lower their importance
+    pub fix: bool,
+    /// How much to lower the importance of stale memories
+    pub downgrade: u8,
+    pub json: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum Problem {
+    Deleted {
+        path: String,
+    },
+    Renamed {
+        path: String,
+        to: String,
+    },
+    Modified {
+        path: String,
+        changed: f64,
+    },
+    LineOutOfRange {
+        path: String,
+        line: usize,
+        lines: usize,
+    },
+    SymbolMissing {
+        symbol: String,
+    },
+}
+
+impl Problem {
+    /// Renames can be repaired; everything else makes the memory suspect.
+    fn is_stale(&self) -> bool {
+        !matches!(self, Problem::Renamed { .. })
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Problem::Deleted { path } => format!("{}: deleted", path),
+            Problem::Renamed { path, to } => format!("{}: renamed to {}", path, to),
+            Problem::Modified { path, changed } => {
+                format!("{}: {:.0}% of lines changed", path, changed * 100.0)
+            }
+            Problem::LineOutOfRange { path, line, lines } => {
+                format!("{}:{}: file now has {} lines", path, line, lines)
+            }
+            Problem::SymbolMissing { symbol } => format!("symbol {}: not found", symbol),
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct Finding {
+    id: Option<String>,
+    content: String,
+    problems: Vec<Problem>,
+}
+
+pub async fn run(options: AuditOptions) -> Result<()> {
+    let root = repo_root();
+    let memories: Vec<Memory> = fetch_memories(false)?
+        .into_iter()
+        .filter(|m| !m.file_refs().is_empty() || !m.symbol_refs().is_empty())
+        .collect();
+
+    let renames = renamed_paths(&root)?;
+    let mut findings = Vec::new();
+    let mut updated = Vec::new();
+
+    for memory in &memories {
+        let problems = audit_memory(&root, memory, &renames, options.threshold)?;
+        if problems.is_empty() {
+            continue;
+        }
+        if options.fix {
+            updated.extend(repair(memory, &problems, options.downgrade));
+        }
+        findings.push(Finding {
+            id: memory.id.clone(),
+            content: memory.content.clone(),
+            problems,
+        });
+    }
+
+    if options.json {
+        let payload = serde_json::json!({
+            "checked": memories.len(),
+            "findings": findings,
+            "fixed": updated.len(),
+        });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+    } else {
+        for finding in &findings {
+            let preview: String = finding
+                .content
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .chars()
+                .take(80)
+                .collect();
+            println!("{} {}", finding.id.as_deref().unwrap_or("-"), preview);
+            for problem in &finding.problems {
+                println!("    {}", problem.describe());
+            }
+        }
+        println!(
+            "{}Checked {} linked memories, {} with stale references",
+            if findings.is_empty() { "" } else { "\n" },
+            memories.len(),
+            findings.len()
+        );
+    }
+
+    if !updated.is_empty() {
+        replace_memories(&updated)?;
+        if !options.json {
+            println!("Updated {} memories", updated.len());
+        }
+    } else if !findings.is_empty() && !options.fix && !options.json {
+        println!("Run with --fix to update renamed paths and flag the rest as stale");
+    }
+    Ok(())
+}
+
+fn audit_memory(
+    root: &Path,
+    memory: &Memory,
+    renames: &HashMap<PathBuf, PathBuf>,
+    threshold: f64,
+) -> Result<Vec<Problem>> {
+    let mut problems = Vec::new();
+    let since = memory.created();
+
+    for file_ref in memory.file_refs() {
+        let path = file_ref.path.display().to_string();
+        let absolute = root.join(&file_ref.path);
+
+        if !absolute.exists() {
+            problems.push(match follow_renames(&file_ref.path, renames) {
+                Some(to) if root.join(&to).exists() => Problem::Renamed {
+                    path,
+                    to: to.display().to_string(),
+                },
+                _ => Problem::Deleted { path },
+            });
+            continue;
+        }
+        if absolute.is_dir() {
+            continue;
+        }
+
+        let lines = std::fs::read_to_string(&absolute)
+            .map(|c| c.lines().count())
+            .unwrap_or_default();
+        if let Some(line) = file_ref.line.filter(|l| *l > lines) {
+            problems.push(Problem::LineOutOfRange {
+                path: path.clone(),
+                line,
+                lines,
+            });
+        }
+
+        if let Some(since) = since {
+            let paths = previous_names(&file_ref.path, renames);
+            let changed = changed_fraction(root, &paths, since, lines)?;
+            if changed >= threshold {
+                problems.push(Problem::Modified { path, changed });
+            }
+        }
+    }
+
+    for symbol in memory.symbol_refs() {
+        if !symbol_exists(root, symbol)? {
+            problems.push(Problem::SymbolMissing {
+                symbol: symbol.to_string(),
+            });
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Point renamed references at their new path; tag and downgrade the rest.
+///
+/// Returns `None` when there is nothing to change, so repeated runs don't
+/// keep lowering the importance of memories already tagged stale.
+fn repair(original: &Memory, problems: &[Problem], downgrade: u8) -> Option<Memory> {
+    let mut memory = original.clone();
+
+    for problem in problems {
+        if let Problem::Renamed { path, to } = problem {
+            for tag in memory.tags.iter_mut() {
+                let Some(mut file_ref) = FileRef::from_tag(tag) else {
+                    continue;
+                };
+                if file_ref.path == Path::new(path) {
+                    file_ref.path = PathBuf::from(to);
+                    *tag = file_ref.tag();
+                }
+            }
+        }
+    }
+
+    if problems.iter().any(Problem::is_stale) && !memory.tags.iter().any(|t| t == STALE_TAG) {
+        memory.tags.push(STALE_TAG.to_string());
+        memory.importance = memory
+            .importance
+            .map(|i| i.saturating_sub(downgrade).max(1));
+    }
+
+    (memory.tags != original.tags).then_some(memory)
+}
+
+/// Map of old path to new path for every rename in the repo's history.
+fn renamed_paths(root: &Path) -> Result<HashMap<PathBuf, PathBuf>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["log", "-M", "--diff-filter=R", "--name-status", "--format="])
+        .output()
+        .context("failed to run git log")?;
+
+    let mut renames = HashMap::new();
+    if !output.status.success() {
+        return Ok(renames);
+    }
+    // git log lists newest first; keep the newest rename for each old path
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let mut parts = line.split('\t');
+        let (Some(status), Some(from), Some(to)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        if status.starts_with('R') {
+            renames
+                .entry(PathBuf::from(from))
+                .or_insert_with(|| PathBuf::from(to));
+        }
+    }
+    Ok(renames)
+}
+
+fn follow_renames(path: &Path, renames: &HashMap<PathBuf, PathBuf>) -> Option<PathBuf> {
+    let mut current = renames.get(path)?.clone();
+    // Bounded walk in case of rename cycles (a -> b -> a)
+    for _ in 0..32 {
+        match renames.get(&current) {
+            Some(next) if next != path => current = next.clone(),
+            _ => break,
+        }
+    }
+    Some(current)
+}
+
+/// `path` and the names it had before being renamed, so a diff limited to
+/// them can still pair up the rename.
+fn previous_names(path: &Path, renames: &HashMap<PathBuf, PathBuf>) -> Vec<PathBuf> {
+    let mut names = vec![path.to_path_buf()];
+    let mut current = path.to_path_buf();
+    while names.len() < 32 {
+        match renames
+            .iter()
+            .find(|(from, to)| **to == current && !names.contains(from))
+        {
+            Some((from, _)) => {
+                current = from.clone();
+                names.push(current.clone());
+            }
+            None => break,
+        }
+    }
+    names
+}
+
+/// Share of the file's lines added or removed since `since`.
+fn changed_fraction(
+    root: &Path,
+    paths: &[PathBuf],
+    since: chrono::DateTime<chrono::Utc>,
+    lines: usize,
+) -> Result<f64> {
+    let base = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args([
+            "rev-list",
+            "-1",
+            &format!("--before={}", since.to_rfc3339()),
+            "HEAD",
+        ])
+        .output()
+        .context("failed to run git rev-list")?;
+    let base = String::from_utf8_lossy(&base.stdout).trim().to_string();
+    if base.is_empty() {
+        return Ok(0.0);
+    }
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["diff", "-M", "--numstat", &base, "--"])
+        .args(paths)
+        .output()
+        .context("failed to run git diff")?;
+
+    let changed: usize = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let added: usize = parts.next()?.parse().ok()?;
+            let removed: usize = parts.next()?.parse().ok()?;
+            Some(added + removed)
+        })
+        .sum();
+
+    Ok((changed as f64 / lines.max(1) as f64).min(1.0))
+}
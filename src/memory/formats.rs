@@ -0,0 +1,581 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::kinds::{self, MemoryKind};
+use super::Memory;
+
+/// Prefix of the line linking an Obsidian note back to its category index.
+const OBSIDIAN_BACKLINK_PREFIX: &str = "Category: [[";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MemoryFormat {
+    /// mmry's native JSON export
+    Json,
+    /// Markdown summary grouped by category (export only)
+    #[value(alias = "markdown")]
+    Md,
+    /// One JSON memory per line
+    Jsonl,
+    /// Comma-separated values with a header row
+    Csv,
+    /// Obsidian vault with one note per memory
+    Obsidian,
+}
+
+impl MemoryFormat {
+    /// Default export location under `.memories/`.
+    pub fn default_export_path(self) -> PathBuf {
+        let memories_dir = PathBuf::from(".memories");
+        match self {
+            MemoryFormat::Json => memories_dir.join("export.json"),
+            MemoryFormat::Md => memories_dir.join("export.md"),
+            MemoryFormat::Jsonl => memories_dir.join("export.jsonl"),
+            MemoryFormat::Csv => memories_dir.join("export.csv"),
+            MemoryFormat::Obsidian => memories_dir.join("obsidian"),
+        }
+    }
+}
+
+/// Guess the format of an import source from its path and contents.
+pub fn detect(path: &Path) -> Result<MemoryFormat> {
+    if path.is_dir() {
+        return Ok(MemoryFormat::Obsidian);
+    }
+
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "json" => return Ok(MemoryFormat::Json),
+        "jsonl" | "ndjson" => return Ok(MemoryFormat::Jsonl),
+        "csv" => return Ok(MemoryFormat::Csv),
+        _ => {}
+    }
+
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let trimmed = content.trim_start();
+    if trimmed.starts_with('[') {
+        return Ok(MemoryFormat::Json);
+    }
+    if trimmed.starts_with('{') {
+        if serde_json::from_str::<serde_json::Value>(trimmed).is_ok() {
+            return Ok(MemoryFormat::Json);
+        }
+        return Ok(MemoryFormat::Jsonl);
+    }
+    // A single note only counts with frontmatter; `memory export` summaries
+    // are Markdown too, but can't be read back
+    if trimmed.starts_with("---") {
+        return Ok(MemoryFormat::Obsidian);
+    }
+    if trimmed.starts_with("# Memories") {
+        return Ok(MemoryFormat::Md);
+    }
+    if trimmed
+        .lines()
+        .next()
+        .is_some_and(|header| header.split(',').any(|c| c.trim() == "content"))
+    {
+        return Ok(MemoryFormat::Csv);
+    }
+
+    anyhow::bail!(
+        "could not detect the format of {} (use --format)",
+        path.display()
+    )
+}
+
+/// Write memories to `output` in the given format.
+pub fn write(format: MemoryFormat, memories: &[Memory], output: &Path) -> Result<()> {
+    match format {
+        MemoryFormat::Json => fs::write(output, serde_json::to_string_pretty(memories)?)?,
+        MemoryFormat::Md => fs::write(output, to_markdown(memories))?,
+        MemoryFormat::Jsonl => fs::write(output, to_jsonl(memories)?)?,
+        MemoryFormat::Csv => write_csv(memories, output)?,
+        MemoryFormat::Obsidian => write_obsidian(memories, output)?,
+    }
+    Ok(())
+}
+
+/// Read memories from `input` in the given format.
+pub fn read(format: MemoryFormat, input: &Path) -> Result<Vec<Memory>> {
+    match format {
+        MemoryFormat::Json => parse_json(&fs::read_to_string(input)?),
+        MemoryFormat::Md => anyhow::bail!(
+            "markdown exports cannot be imported; export with --format obsidian to round-trip"
+        ),
+        MemoryFormat::Jsonl => parse_jsonl(&fs::read_to_string(input)?),
+        MemoryFormat::Csv => read_csv(input),
+        MemoryFormat::Obsidian => read_obsidian(input),
+    }
+}
+
+/// Parse mmry's JSON export, accepting either a bare array or `{ "memories": [...] }`.
+pub fn parse_json(content: &str) -> Result<Vec<Memory>> {
+    let value: serde_json::Value =
+        serde_json::from_str(content).context("failed to parse memory JSON")?;
+    let list = match value {
+        serde_json::Value::Object(mut obj) => obj
+            .remove("memories")
+            .context("expected an array of memories")?,
+        other => other,
+    };
+    serde_json::from_value(list).context("failed to parse memory JSON")
+}
+
+fn to_markdown(memories: &[Memory]) -> String {
+    let mut md = String::new();
+    md.push_str("# Memories\n\n");
+
+    // Structured memories get a section per kind, the rest go by category
+    let mut by_kind: BTreeMap<MemoryKind, Vec<&Memory>> = BTreeMap::new();
+    let mut free_form = Vec::new();
+    for mem in memories {
+        match mem.kind() {
+            Some(kind) => by_kind.entry(kind).or_default().push(mem),
+            None => free_form.push(mem.clone()),
+        }
+    }
+
+    for (kind, mems) in by_kind {
+        md.push_str(&format!("## {}\n\n", kind.heading()));
+        for mem in mems {
+            if let Some(structured) = mem.structured() {
+                md.push_str(&kinds::render_markdown(&structured, mem.importance));
+            }
+        }
+        md.push('\n');
+    }
+
+    for (category, mems) in group_by_category(&free_form) {
+        md.push_str(&format!("## {}\n\n", category));
+        for mem in mems {
+            let importance = mem
+                .importance
+                .map(|i| format!(" [i:{}]", i))
+                .unwrap_or_default();
+            md.push_str(&format!("- {}{}\n", mem.content.trim(), importance));
+        }
+        md.push('\n');
+    }
+
+    md
+}
+
+fn to_jsonl(memories: &[Memory]) -> Result<String> {
+    let mut out = String::new();
+    for mem in memories {
+        out.push_str(&serde_json::to_string(mem)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn parse_jsonl(content: &str) -> Result<Vec<Memory>> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str(line).with_context(|| format!("invalid JSON on line {}", idx + 1))
+        })
+        .collect()
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CsvRow {
+    #[serde(default)]
+    id: Option<String>,
+    content: String,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    tags: Option<String>,
+    #[serde(default)]
+    importance: Option<u8>,
+    #[serde(default)]
+    created_at: Option<String>,
+}
+
+fn write_csv(memories: &[Memory], output: &Path) -> Result<()> {
+    let mut writer = csv::Writer::from_path(output)
+        .with_context(|| format!("failed to create {}", output.display()))?;
+    for mem in memories {
+        writer.serialize(CsvRow {
+            id: mem.id.clone(),
+            content: mem.content.clone(),
+            category: mem.category.clone(),
+            tags: (!mem.tags.is_empty()).then(|| mem.tags.join(",")),
+            importance: mem.importance,
+            created_at: mem.created_at.clone(),
+        })?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn read_csv(input: &Path) -> Result<Vec<Memory>> {
+    let mut reader = csv::Reader::from_path(input)
+        .with_context(|| format!("failed to open {}", input.display()))?;
+    let mut memories = Vec::new();
+    for row in reader.deserialize::<CsvRow>() {
+        let row = row.context("invalid CSV row")?;
+        memories.push(Memory {
+            id: row.id.filter(|s| !s.is_empty()),
+            content: row.content,
+            category: row.category.filter(|s| !s.is_empty()),
+            tags: split_tags(row.tags.as_deref().unwrap_or_default()),
+            importance: row.importance,
+            created_at: row.created_at.filter(|s| !s.is_empty()),
+            ..Default::default()
+        });
+    }
+    Ok(memories)
+}
+
+fn write_obsidian(memories: &[Memory], vault: &Path) -> Result<()> {
+    fs::create_dir_all(vault)?;
+
+    // Obsidian links notes by name, so names are unique across the vault;
+    // each folder is named after its index note
+    let mut used = HashSet::new();
+    for (category, mems) in group_by_category(memories) {
+        let slug = slugify(&category);
+        let base = if slug.is_empty() {
+            "uncategorized".to_string()
+        } else {
+            slug
+        };
+        let index_name = unique_name(&base, &mut used);
+        let folder = vault.join(&index_name);
+        fs::create_dir_all(&folder)?;
+
+        let mut index = format!("---\ntype: category\n---\n\n# {}\n\n", category);
+
+        for mem in mems {
+            let name = unique_note_name(mem, &mut used);
+            index.push_str(&format!("- [[{}]]\n", name));
+            fs::write(
+                folder.join(format!("{}.md", name)),
+                obsidian_note(mem, &index_name),
+            )?;
+        }
+
+        fs::write(folder.join(format!("{}.md", index_name)), index)?;
+    }
+
+    Ok(())
+}
+
+fn obsidian_note(mem: &Memory, index_name: &str) -> String {
+    let mut note = String::from("---\ntype: memory\n");
+    if let Some(id) = &mem.id {
+        note.push_str(&format!("id: {}\n", yaml_string(id)));
+    }
+    if let Some(cat) = &mem.category {
+        note.push_str(&format!("category: {}\n", yaml_string(cat)));
+    }
+    if !mem.tags.is_empty() {
+        note.push_str("tags:\n");
+        for tag in &mem.tags {
+            note.push_str(&format!("  - {}\n", yaml_string(tag)));
+        }
+    }
+    if let Some(importance) = mem.importance {
+        note.push_str(&format!("importance: {}\n", importance));
+    }
+    if let Some(created) = &mem.created_at {
+        note.push_str(&format!("created_at: {}\n", yaml_string(created)));
+    }
+    note.push_str("---\n\n");
+    note.push_str(mem.content.trim());
+    note.push_str(&format!(
+        "\n\n{}{}]]\n",
+        OBSIDIAN_BACKLINK_PREFIX, index_name
+    ));
+    note
+}
+
+fn read_obsidian(input: &Path) -> Result<Vec<Memory>> {
+    let mut notes = Vec::new();
+    if input.is_dir() {
+        collect_notes(input, &mut notes)?;
+        notes.sort();
+    } else {
+        let content = fs::read_to_string(input)
+            .with_context(|| format!("failed to read {}", input.display()))?;
+        if !content.trim_start().starts_with("---") {
+            anyhow::bail!(
+                "{} has no frontmatter, so it isn't an Obsidian note; pass the vault directory",
+                input.display()
+            );
+        }
+        notes.push(input.to_path_buf());
+    }
+
+    let mut memories = Vec::new();
+    for note in notes {
+        let content = fs::read_to_string(&note)
+            .with_context(|| format!("failed to read {}", note.display()))?;
+        let folder = note
+            .parent()
+            .filter(|p| input.is_dir() && *p != input)
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .filter(|n| n != "uncategorized");
+        if let Some(mem) = parse_obsidian_note(&content, folder) {
+            memories.push(mem);
+        }
+    }
+    Ok(memories)
+}
+
+fn collect_notes(dir: &Path, notes: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            collect_notes(&path, notes)?;
+        } else if path.extension().is_some_and(|e| e == "md") {
+            notes.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Parse a single note; returns `None` for category index notes and empty notes.
+fn parse_obsidian_note(content: &str, folder: Option<String>) -> Option<Memory> {
+    let (frontmatter, body) = split_frontmatter(content);
+    if frontmatter
+        .get("type")
+        .and_then(|v| v.first())
+        .map(String::as_str)
+        == Some("category")
+    {
+        return None;
+    }
+
+    // Only the backlink `obsidian_note` appends, not lines the memory has
+    let mut body = body.trim();
+    let (rest, last) = body.rsplit_once('\n').unwrap_or(("", body));
+    if last.starts_with(OBSIDIAN_BACKLINK_PREFIX) && last.ends_with("]]") {
+        body = rest.trim();
+    }
+    if body.is_empty() {
+        return None;
+    }
+
+    let scalar = |key: &str| frontmatter.get(key).and_then(|v| v.first()).cloned();
+
+    Some(Memory {
+        id: scalar("id"),
+        content: body.to_string(),
+        category: scalar("category").or(folder),
+        tags: frontmatter.get("tags").cloned().unwrap_or_default(),
+        importance: scalar("importance").and_then(|i| i.parse().ok()),
+        created_at: scalar("created_at"),
+        ..Default::default()
+    })
+}
+
+/// Minimal YAML frontmatter reader for the keys agntz writes: scalars,
+/// inline lists (`[a, b]`) and block lists (`- a`).
+fn split_frontmatter(content: &str) -> (BTreeMap<String, Vec<String>>, &str) {
+    let mut fields = BTreeMap::new();
+    let Some(rest) = content.strip_prefix("---\n") else {
+        return (fields, content);
+    };
+    let Some(end) = rest.find("\n---") else {
+        return (fields, content);
+    };
+    let body = &rest[end + 4..];
+
+    let mut current: Option<String> = None;
+    for line in rest[..end].lines() {
+        if let Some(item) = line.trim_start().strip_prefix("- ") {
+            if let Some(key) = &current {
+                fields
+                    .entry(key.clone())
+                    .or_insert_with(Vec::new)
+                    .push(yaml_unquote(item));
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_string();
+        let value = value.trim();
+        let values = if let Some(inner) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']'))
+        {
+            inner
+                .split(',')
+                .map(yaml_unquote)
+                .filter(|v| !v.is_empty())
+                .collect()
+        } else if value.is_empty() {
+            Vec::new()
+        } else {
+            vec![yaml_unquote(value)]
+        };
+        fields.insert(key.clone(), values);
+        current = Some(key);
+    }
+
+    (fields, body)
+}
+
+fn yaml_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| format!("\"{}\"", value))
+}
+
+fn yaml_unquote(value: &str) -> String {
+    let value = value.trim();
+    if value.starts_with('"') {
+        if let Ok(parsed) = serde_json::from_str::<String>(value) {
+            return parsed;
+        }
+    }
+    value.trim_matches(['"', '\'']).to_string()
+}
+
+fn unique_note_name(mem: &Memory, used: &mut HashSet<String>) -> String {
+    let words = mem
+        .content
+        .split_whitespace()
+        .take(8)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut base = slugify(&words);
+    if base.is_empty() {
+        base = "memory".to_string();
+    }
+    if let Some(id) = &mem.id {
+        let short: String = id.chars().filter(|c| c.is_alphanumeric()).take(8).collect();
+        if !short.is_empty() {
+            base = format!("{}-{}", base, short);
+        }
+    }
+
+    unique_name(&base, used)
+}
+
+/// `base`, or `base-2`, `base-3`... if it is already taken.
+fn unique_name(base: &str, used: &mut HashSet<String>) -> String {
+    let mut name = base.to_string();
+    let mut n = 2;
+    while !used.insert(name.clone()) {
+        name = format!("{}-{}", base, n);
+        n += 1;
+    }
+    name
+}
+
+fn slugify(value: &str) -> String {
+    let mut slug = String::new();
+    for c in value.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.ends_with('-') && !slug.is_empty() {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+pub fn split_tags(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+fn group_by_category(memories: &[Memory]) -> BTreeMap<String, Vec<&Memory>> {
+    let mut by_category: BTreeMap<String, Vec<&Memory>> = BTreeMap::new();
+    for mem in memories {
+        let cat = mem
+            .category
+            .clone()
+            .unwrap_or_else(|| "uncategorized".to_string());
+        by_category.entry(cat).or_default().push(mem);
+    }
+    by_category
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_frontmatter_reads_scalars_and_lists() {
+        let note = "---\ntype: memory\nid: \"m-1\"\ntags:\n  - api\n  - \"auth\"\naliases: [a, 'b']\nempty:\n---\n\nBody\n";
+        let (fields, body) = split_frontmatter(note);
+        assert_eq!(fields["type"], ["memory"]);
+        assert_eq!(fields["id"], ["m-1"]);
+        assert_eq!(fields["tags"], ["api", "auth"]);
+        assert_eq!(fields["aliases"], ["a", "b"]);
+        assert!(fields["empty"].is_empty());
+        assert_eq!(body.trim(), "Body");
+    }
+
+    #[test]
+    fn split_frontmatter_without_frontmatter() {
+        let (fields, body) = split_frontmatter("# Title\n\ntext");
+        assert!(fields.is_empty());
+        assert_eq!(body, "# Title\n\ntext");
+
+        let (fields, body) = split_frontmatter("---\nunterminated: yes\n");
+        assert!(fields.is_empty());
+        assert_eq!(body, "---\nunterminated: yes\n");
+    }
+
+    #[test]
+    fn obsidian_note_round_trips() {
+        let mem = Memory {
+            id: Some("m-1".to_string()),
+            content: "Use SQLite\nCategory: [[not-a-backlink]] is kept".to_string(),
+            category: Some("Foo Bar".to_string()),
+            tags: vec!["db".to_string()],
+            importance: Some(7),
+            ..Default::default()
+        };
+        let parsed = parse_obsidian_note(&obsidian_note(&mem, "foo-bar"), None).unwrap();
+        assert_eq!(parsed.id, mem.id);
+        assert_eq!(parsed.content, mem.content);
+        assert_eq!(parsed.category, mem.category);
+        assert_eq!(parsed.tags, mem.tags);
+        assert_eq!(parsed.importance, mem.importance);
+    }
+
+    #[test]
+    fn obsidian_folders_are_unique_across_categories() {
+        let vault = std::env::temp_dir().join(format!("agntz-vault-{}", std::process::id()));
+        let memories = ["Foo Bar", "foo-bar"].map(|category| Memory {
+            content: format!("note in {}", category),
+            category: Some(category.to_string()),
+            ..Default::default()
+        });
+        write_obsidian(&memories, &vault).unwrap();
+        let mut folders: Vec<String> = fs::read_dir(&vault)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        folders.sort();
+        let back = read_obsidian(&vault).unwrap();
+        fs::remove_dir_all(&vault).unwrap();
+
+        assert_eq!(folders, ["foo-bar", "foo-bar-2"]);
+        assert_eq!(back.len(), 2);
+    }
+}
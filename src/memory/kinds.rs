@@ -0,0 +1,345 @@
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+
+use super::Memory;
+use crate::text;
+
+/// Tag prefix marking a memory's kind (`kind:decision`).
+const KIND_TAG_PREFIX: &str = "kind:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum MemoryKind {
+    /// A choice that was made, with its rationale and the alternatives
+    Decision,
+    /// How things are done in this repo
+    Convention,
+    /// A trap that cost someone time
+    Gotcha,
+    /// An exact invocation worth remembering
+    Command,
+    /// Anything else that is simply true
+    Fact,
+}
+
+impl MemoryKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MemoryKind::Decision => "decision",
+            MemoryKind::Convention => "convention",
+            MemoryKind::Gotcha => "gotcha",
+            MemoryKind::Command => "command",
+            MemoryKind::Fact => "fact",
+        }
+    }
+
+    pub fn heading(self) -> &'static str {
+        match self {
+            MemoryKind::Decision => "Decisions",
+            MemoryKind::Convention => "Conventions",
+            MemoryKind::Gotcha => "Gotchas",
+            MemoryKind::Command => "Commands",
+            MemoryKind::Fact => "Facts",
+        }
+    }
+
+    pub fn tag(self) -> String {
+        format!("{}{}", KIND_TAG_PREFIX, self.as_str())
+    }
+
+    /// Field labels that belong to this kind, in display order.
+    fn labels(self) -> &'static [&'static str] {
+        match self {
+            MemoryKind::Decision => &["Rationale", "Alternatives"],
+            MemoryKind::Command => &["Command", "Workdir"],
+            _ => &[],
+        }
+    }
+}
+
+/// Kind-specific options for `memory add`.
+#[derive(Args, Debug, Default)]
+pub struct KindFields {
+    /// Memory kind
+    #[arg(short, long, value_enum)]
+    pub kind: Option<MemoryKind>,
+    /// Why the decision was made (decision)
+    #[arg(long)]
+    pub rationale: Option<String>,
+    /// An option that was considered and rejected (decision, repeatable)
+    #[arg(long = "alternative")]
+    pub alternatives: Vec<String>,
+    /// The exact command line (command)
+    #[arg(long)]
+    pub invocation: Option<String>,
+    /// Directory to run the command in, relative to the repo root (command)
+    #[arg(long)]
+    pub workdir: Option<String>,
+}
+
+/// A memory's kind and its structured fields, parsed back out of the content.
+pub struct Structured<'a> {
+    pub kind: MemoryKind,
+    pub summary: String,
+    pub fields: Vec<(&'a str, &'a str)>,
+}
+
+/// Build the content and kind tag for a new memory.
+///
+/// Fields are stored as `Label: value` lines after the summary so they stay
+/// searchable and readable in tools that know nothing about kinds.
+pub fn compose(summary: String, fields: &KindFields) -> Result<(String, Option<String>)> {
+    let Some(kind) = fields.kind else {
+        if fields.rationale.is_some()
+            || !fields.alternatives.is_empty()
+            || fields.invocation.is_some()
+            || fields.workdir.is_some()
+        {
+            anyhow::bail!("--rationale, --alternative, --invocation and --workdir need --kind");
+        }
+        return Ok((summary, None));
+    };
+
+    let mut lines = Vec::new();
+    match kind {
+        MemoryKind::Decision => {
+            if fields.invocation.is_some() || fields.workdir.is_some() {
+                anyhow::bail!("--invocation and --workdir only apply to --kind command");
+            }
+            if let Some(rationale) = &fields.rationale {
+                lines.push(format!("Rationale: {}", rationale));
+            }
+            if !fields.alternatives.is_empty() {
+                lines.push(format!("Alternatives: {}", fields.alternatives.join("; ")));
+            }
+        }
+        MemoryKind::Command => {
+            if fields.rationale.is_some() || !fields.alternatives.is_empty() {
+                anyhow::bail!("--rationale and --alternative only apply to --kind decision");
+            }
+            let Some(invocation) = &fields.invocation else {
+                anyhow::bail!("--kind command needs --invocation");
+            };
+            lines.push(format!("Command: {}", invocation));
+            if let Some(workdir) = &fields.workdir {
+                lines.push(format!("Workdir: {}", workdir));
+            }
+        }
+        _ => {
+            if fields.rationale.is_some()
+                || !fields.alternatives.is_empty()
+                || fields.invocation.is_some()
+                || fields.workdir.is_some()
+            {
+                anyhow::bail!("--kind {} takes no extra fields", kind.as_str());
+            }
+        }
+    }
+
+    let content = if lines.is_empty() {
+        summary
+    } else {
+        format!("{}\n\n{}", summary.trim_end(), lines.join("\n"))
+    };
+    Ok((content, Some(kind.tag())))
+}
+
+impl Memory {
+    pub fn kind(&self) -> Option<MemoryKind> {
+        self.tags.iter().find_map(|tag| {
+            let name = tag.strip_prefix(KIND_TAG_PREFIX)?;
+            MemoryKind::from_str(name, true).ok()
+        })
+    }
+
+    pub fn structured(&self) -> Option<Structured<'_>> {
+        let kind = self.kind()?;
+        let content = self.content.trim();
+        let labels = kind.labels();
+
+        // Field lines are the trailing block of known `Label: value` lines
+        let lines: Vec<&str> = content.lines().collect();
+        let mut summary_len = lines.len();
+        let mut fields = Vec::new();
+        while summary_len > 0 {
+            let Some((label, value)) = lines[summary_len - 1].split_once(": ") else {
+                break;
+            };
+            if !labels.contains(&label) {
+                break;
+            }
+            fields.push((label, value.trim()));
+            summary_len -= 1;
+        }
+        fields.reverse();
+
+        Some(Structured {
+            kind,
+            summary: lines[..summary_len].join("\n").trim_end().to_string(),
+            fields,
+        })
+    }
+}
+
+/// Terminal rendering for `memory list`.
+pub fn render_list(memories: &[Memory]) -> String {
+    let mut out = String::new();
+    for mem in memories {
+        let id = mem.id.as_deref().unwrap_or("-");
+        let importance = mem
+            .importance
+            .map(|i| format!(" [i:{}]", i))
+            .unwrap_or_default();
+        let category = mem
+            .category
+            .as_deref()
+            .map(|c| format!(" ({})", c))
+            .unwrap_or_default();
+
+        match mem.structured() {
+            Some(s) => {
+                out.push_str(&format!(
+                    "{} [{}] {}{}{}\n",
+                    id,
+                    s.kind.as_str(),
+                    text::first_line(&s.summary, 120),
+                    category,
+                    importance
+                ));
+                for (label, value) in &s.fields {
+                    match *label {
+                        "Command" => out.push_str(&format!("    $ {}\n", value)),
+                        "Workdir" => out.push_str(&format!("      in {}\n", value)),
+                        "Alternatives" => {
+                            out.push_str(&format!("    rejected: {}\n", value.replace("; ", ", ")))
+                        }
+                        _ => out.push_str(&format!("    why: {}\n", value)),
+                    }
+                }
+            }
+            None => out.push_str(&format!(
+                "{} {}{}{}\n",
+                id,
+                text::first_line(&mem.content, 120),
+                category,
+                importance
+            )),
+        }
+
+        let refs: Vec<String> = mem
+            .file_refs()
+            .iter()
+            .map(|r| r.to_string())
+            .chain(mem.symbol_refs().iter().map(|s| format!("symbol {}", s)))
+            .chain(mem.task_refs().iter().map(|t| format!("task {}", t)))
+            .collect();
+        if !refs.is_empty() {
+            out.push_str(&format!("    refs: {}\n", refs.join(", ")));
+        }
+    }
+    out
+}
+
+/// Markdown rendering of one structured memory, for exports.
+pub fn render_markdown(s: &Structured, importance: Option<u8>) -> String {
+    let importance = importance
+        .map(|i| format!(" [i:{}]", i))
+        .unwrap_or_default();
+    let mut md = format!("- {}{}\n", s.summary.replace('\n', "\n  "), importance);
+    for (label, value) in &s.fields {
+        match *label {
+            "Command" => md.push_str(&format!("  ```sh\n  {}\n  ```\n", value)),
+            "Alternatives" => {
+                md.push_str("  - **Alternatives considered:**\n");
+                for alt in value.split("; ") {
+                    md.push_str(&format!("    - {}\n", alt));
+                }
+            }
+            _ => md.push_str(&format!("  - **{}:** {}\n", label, value)),
+        }
+    }
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory(content: &str, tags: &[&str]) -> Memory {
+        Memory {
+            content: content.to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn structured_reads_back_composed_fields() {
+        let fields = KindFields {
+            kind: Some(MemoryKind::Decision),
+            rationale: Some("no server".to_string()),
+            alternatives: vec!["Postgres".to_string(), "Redis".to_string()],
+            ..Default::default()
+        };
+        let (content, tag) = compose("Use SQLite for state".to_string(), &fields).unwrap();
+        let mem = memory(&content, &[tag.as_deref().unwrap()]);
+
+        let structured = mem.structured().unwrap();
+        assert_eq!(structured.kind, MemoryKind::Decision);
+        assert_eq!(structured.summary, "Use SQLite for state");
+        assert_eq!(
+            structured.fields,
+            [
+                ("Rationale", "no server"),
+                ("Alternatives", "Postgres; Redis")
+            ]
+        );
+    }
+
+    #[test]
+    fn structured_only_takes_the_trailing_known_fields() {
+        let mem = memory(
+            "Run the e2e suite\nNote: needs docker\n\nCommand: just e2e\nWorkdir: web",
+            &["kind:command"],
+        );
+        let structured = mem.structured().unwrap();
+        assert_eq!(structured.summary, "Run the e2e suite\nNote: needs docker");
+        assert_eq!(
+            structured.fields,
+            [("Command", "just e2e"), ("Workdir", "web")]
+        );
+
+        // Labels of another kind stay part of the summary
+        let mem = memory("Flaky on CI\nRationale: unknown", &["kind:gotcha"]);
+        let structured = mem.structured().unwrap();
+        assert_eq!(structured.summary, "Flaky on CI\nRationale: unknown");
+        assert!(structured.fields.is_empty());
+    }
+
+    #[test]
+    fn structured_needs_a_kind_tag() {
+        assert!(memory("Command: just e2e", &["kind:unknown", "api"])
+            .structured()
+            .is_none());
+        assert_eq!(
+            memory("x", &["api", "KIND:Fact"]).kind(),
+            None,
+            "the prefix is case-sensitive"
+        );
+    }
+
+    #[test]
+    fn compose_rejects_fields_of_another_kind() {
+        let fields = KindFields {
+            kind: Some(MemoryKind::Decision),
+            invocation: Some("just e2e".to_string()),
+            ..Default::default()
+        };
+        assert!(compose("x".to_string(), &fields).is_err());
+
+        let fields = KindFields {
+            kind: Some(MemoryKind::Command),
+            ..Default::default()
+        };
+        assert!(compose("x".to_string(), &fields).is_err());
+    }
+}
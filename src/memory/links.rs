@@ -0,0 +1,203 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use super::{fetch_memories, kinds, repo_root, Memory};
+
+const FILE_TAG_PREFIX: &str = "file:";
+const SYMBOL_TAG_PREFIX: &str = "symbol:";
+const TASK_TAG_PREFIX: &str = "task:";
+
+/// References from a new memory to code and tasks.
+#[derive(Args, Debug, Default)]
+pub struct Links {
+    /// File the memory is about, as path or path:line (repeatable)
+    #[arg(long = "file")]
+    pub files: Vec<String>,
+    /// Code symbol the memory is about (repeatable)
+    #[arg(long = "symbol")]
+    pub symbols: Vec<String>,
+    /// trx task the memory relates to (repeatable)
+    #[arg(long = "task")]
+    pub tasks: Vec<String>,
+}
+
+/// A file reference, stored relative to the repo root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileRef {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+}
+
+impl FileRef {
+    fn parse(value: &str) -> FileRef {
+        if let Some((path, line)) = value.rsplit_once(':') {
+            if let Ok(line) = line.parse() {
+                return FileRef {
+                    path: PathBuf::from(path),
+                    line: Some(line),
+                };
+            }
+        }
+        FileRef {
+            path: PathBuf::from(value),
+            line: None,
+        }
+    }
+
+    pub fn from_tag(tag: &str) -> Option<FileRef> {
+        tag.strip_prefix(FILE_TAG_PREFIX).map(FileRef::parse)
+    }
+
+    pub fn tag(&self) -> String {
+        match self.line {
+            Some(line) => format!("{}{}:{}", FILE_TAG_PREFIX, self.path.display(), line),
+            None => format!("{}{}", FILE_TAG_PREFIX, self.path.display()),
+        }
+    }
+}
+
+impl std::fmt::Display for FileRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}", self.path.display(), line),
+            None => write!(f, "{}", self.path.display()),
+        }
+    }
+}
+
+impl Memory {
+    pub fn file_refs(&self) -> Vec<FileRef> {
+        self.tags
+            .iter()
+            .filter_map(|tag| FileRef::from_tag(tag))
+            .collect()
+    }
+
+    pub fn symbol_refs(&self) -> Vec<&str> {
+        self.tags
+            .iter()
+            .filter_map(|tag| tag.strip_prefix(SYMBOL_TAG_PREFIX))
+            .collect()
+    }
+
+    pub fn task_refs(&self) -> Vec<&str> {
+        self.tags
+            .iter()
+            .filter_map(|tag| tag.strip_prefix(TASK_TAG_PREFIX))
+            .collect()
+    }
+}
+
+/// Check that every reference exists and turn them into tags.
+pub fn resolve(links: &Links) -> Result<Vec<String>> {
+    let mut tags = Vec::new();
+    if links.files.is_empty() && links.symbols.is_empty() && links.tasks.is_empty() {
+        return Ok(tags);
+    }
+    let root = repo_root().canonicalize().unwrap_or_else(|_| repo_root());
+
+    for value in &links.files {
+        let mut file_ref = FileRef::parse(value);
+        file_ref.path = repo_relative(&root, &file_ref.path)?;
+        let absolute = root.join(&file_ref.path);
+        if !absolute.exists() {
+            anyhow::bail!("--file {}: no such file or directory", value);
+        }
+        if let Some(line) = file_ref.line {
+            let content = std::fs::read_to_string(&absolute)
+                .with_context(|| format!("--file {}: cannot read file", value))?;
+            let count = content.lines().count();
+            if line == 0 || line > count {
+                anyhow::bail!("--file {}: file has {} lines", value, count);
+            }
+        }
+        tags.push(file_ref.tag());
+    }
+
+    for symbol in &links.symbols {
+        if !symbol_exists(&root, symbol)? {
+            anyhow::bail!("--symbol {}: not found in tracked files", symbol);
+        }
+        tags.push(format!("{}{}", SYMBOL_TAG_PREFIX, symbol));
+    }
+
+    for task in &links.tasks {
+        if !task_exists(task)? {
+            anyhow::bail!("--task {}: no such trx task", task);
+        }
+        tags.push(format!("{}{}", TASK_TAG_PREFIX, task));
+    }
+
+    Ok(tags)
+}
+
+/// Make `path` (relative to the current directory) relative to the repo root.
+pub fn repo_relative(root: &Path, path: &Path) -> Result<PathBuf> {
+    let cwd = std::env::current_dir()?;
+    let absolute = normalize(&cwd.join(path));
+    let absolute = absolute.canonicalize().unwrap_or(absolute);
+    absolute
+        .strip_prefix(root)
+        .map(Path::to_path_buf)
+        .map_err(|_| anyhow::anyhow!("{} is outside the repository", path.display()))
+}
+
+/// Resolve `.` and `..` without touching the filesystem, so paths of
+/// deleted files can still be matched.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+pub fn symbol_exists(root: &Path, symbol: &str) -> Result<bool> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["grep", "-q", "-w", "-F", "-e", symbol])
+        .output()
+        .context("failed to run git grep")?;
+    Ok(output.status.success())
+}
+
+fn task_exists(id: &str) -> Result<bool> {
+    let output = Command::new("trx")
+        .args(["show", id])
+        .output()
+        .context("failed to run trx - is trx installed?")?;
+    Ok(output.status.success())
+}
+
+/// `memory for <path>`: memories attached to a file, or to anything under a directory.
+pub async fn handle_for(path: PathBuf, json: bool) -> Result<()> {
+    let root = repo_root().canonicalize().unwrap_or_else(|_| repo_root());
+    let target = repo_relative(&root, &path)?;
+
+    let memories: Vec<Memory> = fetch_memories(false)?
+        .into_iter()
+        .filter(|m| m.file_refs().iter().any(|r| r.path.starts_with(&target)))
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&memories)?);
+        return Ok(());
+    }
+
+    if memories.is_empty() {
+        println!("No memories attached to {}", path.display());
+        return Ok(());
+    }
+
+    print!("{}", kinds::render_list(&memories));
+    Ok(())
+}
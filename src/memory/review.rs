@@ -0,0 +1,305 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::process::Command;
+
+use super::{
+    fetch_with_pending, mmry_command, replace_memories, run_mmry_quiet, safety, temp_path, Memory,
+};
+use crate::agent::{self, Attribution};
+use crate::config::Config;
+
+/// Options for `memory review`. Without any, pending memories are reviewed
+/// one by one (or listed when stdin is not a terminal).
+#[derive(Args, Debug, Default)]
+pub struct ReviewArgs {
+    /// List pending memories without prompting
+    #[arg(long)]
+    list: bool,
+    /// Accept a pending memory (repeatable)
+    #[arg(long, value_name = "ID")]
+    accept: Vec<String>,
+    /// Reject a pending memory (repeatable)
+    #[arg(long, value_name = "ID")]
+    reject: Vec<String>,
+    /// Accept every pending memory
+    #[arg(long, conflicts_with = "reject_all")]
+    accept_all: bool,
+    /// Reject every pending memory
+    #[arg(long)]
+    reject_all: bool,
+    /// Output the pending list as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+/// Tag marking memories from agents that nobody has accepted yet.
+///
+/// Pending memories live in the store, but reads, exports and `memory sync`
+/// leave them out until they are accepted.
+pub const PENDING_TAG: &str = "review:pending";
+
+impl Memory {
+    pub fn is_pending(&self) -> bool {
+        self.tags.iter().any(|tag| tag == PENDING_TAG)
+    }
+}
+
+/// Whether review mode is on. Memories only become pending then, so reads
+/// that mmry could answer directly only need filtering in review mode.
+pub fn enabled() -> bool {
+    Config::load().is_ok_and(|config| config.memory.review)
+}
+
+/// Add a memory from an agent to the store, tagged for review.
+pub fn enqueue(
+    content: String,
+    category: Option<String>,
+    tags: Option<String>,
+    importance: Option<u8>,
+    attribution: Attribution,
+) -> Result<()> {
+    let tags = match tags {
+        Some(tags) => format!("{},{}", tags, PENDING_TAG),
+        None => PENDING_TAG.to_string(),
+    };
+    let mut args = vec!["add".to_string(), content, "-t".to_string(), tags];
+    if let Some(category) = category {
+        args.push("-c".to_string());
+        args.push(category);
+    }
+    if let Some(importance) = importance {
+        args.push("-i".to_string());
+        args.push(importance.to_string());
+    }
+
+    let mut cmd = mmry_command(&args)?;
+    attribution.apply("mmry", &mut cmd);
+    let output = cmd
+        .output()
+        .context("failed to run mmry - is mmry installed?")?;
+    if !output.status.success() {
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        anyhow::bail!("mmry command failed");
+    }
+
+    println!("Queued memory for review (agntz memory review)");
+    Ok(())
+}
+
+pub async fn run(args: ReviewArgs) -> Result<()> {
+    let mut queue: Vec<Memory> = fetch_with_pending(false)?
+        .into_iter()
+        .filter(Memory::is_pending)
+        .collect();
+
+    let batch =
+        args.accept_all || args.reject_all || !args.accept.is_empty() || !args.reject.is_empty();
+    let listing = args.list || args.json || !std::io::stdin().is_terminal();
+    if batch || !listing {
+        // Otherwise an agent could approve its own memories
+        if let Some(agent) = agent::detect() {
+            anyhow::bail!(
+                "memory review needs a human, but this looks like {} ({}); use --list to see the queue",
+                agent.harness,
+                agent.evidence.join(", ")
+            );
+        }
+    }
+
+    if batch {
+        for id in args.accept.iter().chain(&args.reject) {
+            if !queue.iter().any(|m| m.id.as_ref() == Some(id)) {
+                anyhow::bail!("no pending memory with ID {}", id);
+            }
+        }
+        let mut accepted = Vec::new();
+        for memory in queue {
+            let id = memory.id.clone().unwrap_or_default();
+            if args.accept_all || args.accept.contains(&id) {
+                accepted.push(memory);
+            } else if args.reject_all || args.reject.contains(&id) {
+                reject(&id)?;
+            }
+        }
+        return accept(&accepted);
+    }
+
+    if listing {
+        return list(&queue, args.json);
+    }
+
+    if queue.is_empty() {
+        println!("No memories waiting for review");
+        return Ok(());
+    }
+
+    let total = queue.len();
+    let mut edited = vec![false; total];
+    let mut reviewed = 0;
+    let mut index = 0;
+    while index < queue.len() {
+        let memory = &mut queue[index];
+        println!("\n({}/{})", reviewed + 1, total);
+        print!("{}", describe(memory));
+
+        match prompt("[a]ccept, [e]dit, [r]eject, [s]kip, [q]uit?")?.as_str() {
+            "a" => {
+                let memory = queue.remove(index);
+                edited.remove(index);
+                if let Err(err) = accept(std::slice::from_ref(&memory)) {
+                    eprintln!("{:#}", err);
+                }
+                reviewed += 1;
+            }
+            "e" => {
+                let content = edit(&memory.content)?;
+                if content.trim().is_empty() {
+                    println!("Empty content, keeping the original");
+                    continue;
+                }
+                match safety::enforce(content, &Config::load()?.memory.safety) {
+                    Ok(content) => {
+                        memory.content = content;
+                        edited[index] = true;
+                    }
+                    Err(err) => eprintln!("{:#}", err),
+                }
+                // Show the edited version and ask again
+                continue;
+            }
+            "r" => {
+                let memory = queue.remove(index);
+                edited.remove(index);
+                reject(memory.id.as_deref().unwrap_or_default())?;
+                reviewed += 1;
+            }
+            "q" => break,
+            _ => {
+                index += 1;
+                reviewed += 1;
+            }
+        }
+    }
+
+    // Keep edits to memories that are still pending
+    let changed: Vec<Memory> = queue
+        .iter()
+        .zip(&edited)
+        .filter(|(_, edited)| **edited)
+        .map(|(memory, _)| memory.clone())
+        .collect();
+    if !changed.is_empty() {
+        replace_memories(&changed)?;
+    }
+    if !queue.is_empty() {
+        println!("\n{} memories still waiting for review", queue.len());
+    }
+    Ok(())
+}
+
+fn list(queue: &[Memory], json: bool) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(queue)?);
+        return Ok(());
+    }
+    if queue.is_empty() {
+        println!("No memories waiting for review");
+        return Ok(());
+    }
+    for memory in queue {
+        print!("{}", describe(memory));
+    }
+    Ok(())
+}
+
+fn describe(memory: &Memory) -> String {
+    let mut out = format!(
+        "{} from {}",
+        memory.id.as_deref().unwrap_or("-"),
+        memory.agent().unwrap_or("unknown agent")
+    );
+    if let Some(created) = memory.created() {
+        out.push_str(&format!(" at {}", created.format("%Y-%m-%d %H:%M")));
+    }
+    out.push('\n');
+    if let Some(category) = &memory.category {
+        out.push_str(&format!("  category: {}\n", category));
+    }
+    let tags: Vec<&str> = memory
+        .tags
+        .iter()
+        .map(String::as_str)
+        .filter(|tag| *tag != PENDING_TAG)
+        .collect();
+    if !tags.is_empty() {
+        out.push_str(&format!("  tags: {}\n", tags.join(",")));
+    }
+    if let Some(importance) = memory.importance {
+        out.push_str(&format!("  importance: {}\n", importance));
+    }
+    for line in memory.content.lines() {
+        out.push_str(&format!("  | {}\n", line));
+    }
+    out
+}
+
+/// Drop the pending tag, keeping the memories' IDs and their agents.
+fn accept(memories: &[Memory]) -> Result<()> {
+    if memories.is_empty() {
+        return Ok(());
+    }
+    let accepted: Vec<Memory> = memories
+        .iter()
+        .map(|memory| {
+            let mut memory = memory.clone();
+            memory.tags.retain(|tag| tag != PENDING_TAG);
+            memory
+        })
+        .collect();
+    replace_memories(&accepted)?;
+    for memory in memories {
+        println!("Accepted {}", memory.id.as_deref().unwrap_or("-"));
+    }
+    Ok(())
+}
+
+fn reject(id: &str) -> Result<()> {
+    run_mmry_quiet(&["rm".to_string(), id.to_string()])?;
+    println!("Rejected {}", id);
+    Ok(())
+}
+
+fn prompt(question: &str) -> Result<String> {
+    print!("{} ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().to_lowercase())
+}
+
+/// Open the content in `$VISUAL` / `$EDITOR` and return what was saved.
+fn edit(content: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let file = temp_path("review_edit.md");
+    fs::write(&file, content)?;
+
+    // Through the shell, so editors configured with flags (`code -w`) work
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(&file)
+        .status()
+        .with_context(|| format!("failed to run editor '{}'", editor))?;
+    let edited = fs::read_to_string(&file);
+    fs::remove_file(&file).ok();
+
+    if !status.success() {
+        anyhow::bail!("editor exited with {}", status);
+    }
+    Ok(edited?)
+}
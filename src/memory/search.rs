@@ -0,0 +1,425 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use clap::Args;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::process::Command;
+use tokio::task::JoinSet;
+
+use super::{mmry_command_in, review, run_mmry, store_name, Memory};
+use crate::text;
+
+pub struct SearchOptions {
+    pub query: String,
+    pub mode: String,
+    pub limit: usize,
+    pub json: bool,
+    pub stores: Vec<String>,
+    pub all_stores: bool,
+    pub filters: MemoryFilters,
+}
+
+/// Filters applied to search results.
+///
+/// The ones `mmry search` supports are passed to it; the rest are applied to
+/// the parsed results here, fetching more until enough of them match.
+#[derive(Args, Clone, Debug, Default)]
+pub struct MemoryFilters {
+    /// Only memories in this category
+    #[arg(short, long)]
+    pub category: Option<String>,
+    /// Only memories carrying all of these tags (repeatable or comma-separated)
+    #[arg(short, long = "tag", value_delimiter = ',')]
+    pub tags: Vec<String>,
+    /// Only memories with at least this importance
+    #[arg(long)]
+    pub min_importance: Option<u8>,
+    /// Only memories stored by this agent
+    #[arg(long)]
+    pub agent: Option<String>,
+    /// Only memories stored during this agent session
+    #[arg(long)]
+    pub session: Option<String>,
+    /// Only memories created after this time (2026-03-01, 7d, 12h, ...)
+    #[arg(long, value_parser = crate::time::parse_time)]
+    pub since: Option<DateTime<Utc>>,
+    /// Only memories created before this time
+    #[arg(long, value_parser = crate::time::parse_time)]
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl MemoryFilters {
+    pub fn is_empty(&self) -> bool {
+        self.category.is_none()
+            && self.tags.is_empty()
+            && self.min_importance.is_none()
+            && self.agent.is_none()
+            && self.session.is_none()
+            && self.since.is_none()
+            && self.until.is_none()
+    }
+
+    pub fn matches(&self, memory: &Memory) -> bool {
+        if let Some(category) = &self.category {
+            if memory.category.as_deref() != Some(category.as_str()) {
+                return false;
+            }
+        }
+        if !self.tags.iter().all(|tag| memory.tags.contains(tag)) {
+            return false;
+        }
+        if let Some(min) = self.min_importance {
+            if memory.importance.is_none_or(|i| i < min) {
+                return false;
+            }
+        }
+        if let Some(agent) = &self.agent {
+            if memory.agent() != Some(agent.as_str()) {
+                return false;
+            }
+        }
+        if let Some(session) = &self.session {
+            if !memory.in_session(session) {
+                return false;
+            }
+        }
+        if self.since.is_some() || self.until.is_some() {
+            let Some(created) = memory.created() else {
+                return false;
+            };
+            if self.since.is_some_and(|since| created < since)
+                || self.until.is_some_and(|until| created > until)
+            {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// A search result, labelled with the store it came from when federating.
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryHit {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub store: Option<String>,
+    pub score: f32,
+    #[serde(flatten)]
+    pub memory: Memory,
+}
+
+pub async fn run(options: SearchOptions) -> Result<()> {
+    let mut stores = if options.all_stores {
+        list_stores()?
+    } else {
+        options.stores.clone()
+    };
+    let federated = !stores.is_empty();
+
+    // Unfiltered single-store searches are passed straight through to mmry,
+    // unless memories pending review have to be left out
+    if !federated && options.filters.is_empty() && !review::enabled() {
+        let mut args = search_args(&options);
+        args.extend(["--limit".to_string(), options.limit.to_string()]);
+        if options.json {
+            args.push("--json".to_string());
+        }
+        return run_mmry(&args);
+    }
+
+    if !federated {
+        stores.push(store_name()?.unwrap_or_default());
+    }
+
+    let (pushed, remaining) = push_down(&options.filters);
+    let mut args = search_args(&options);
+    args.extend(pushed);
+    let mut hits = fetch_hits(&stores, &args, options.limit, &remaining).await?;
+    if !federated {
+        for hit in &mut hits {
+            hit.store = None;
+        }
+    }
+
+    if options.json {
+        let payload = serde_json::json!({ "hits": hits });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+        return Ok(());
+    }
+
+    print_hits(&hits);
+    Ok(())
+}
+
+/// Search the current store, for callers that merge memories with other
+/// results.
+pub async fn search_memories(query: &str, limit: usize) -> Result<Vec<MemoryHit>> {
+    let store = store_name()?.unwrap_or_default();
+    let args = [
+        "search".to_string(),
+        query.to_string(),
+        "--mode".to_string(),
+        "hybrid".to_string(),
+    ];
+    let mut hits = fetch_hits(&[store], &args, limit, &MemoryFilters::default()).await?;
+    for hit in &mut hits {
+        hit.store = None;
+    }
+    Ok(hits)
+}
+
+/// Search with growing limits until `limit` hits pass `filters` or mmry
+/// returns fewer than asked for. Memories pending review are left out.
+async fn fetch_hits(
+    stores: &[String],
+    search_args: &[String],
+    limit: usize,
+    filters: &MemoryFilters,
+) -> Result<Vec<MemoryHit>> {
+    let mut fetch_limit = limit.max(1);
+    let mut open = stores.to_vec();
+    let mut by_store: HashMap<String, Vec<MemoryHit>> = HashMap::new();
+    loop {
+        let mut args = search_args.to_vec();
+        args.extend(["--limit".to_string(), fetch_limit.to_string()]);
+
+        let mut fetched: HashMap<String, Vec<MemoryHit>> = HashMap::new();
+        for hit in search_stores(&open, &args).await? {
+            fetched
+                .entry(hit.store.clone().unwrap_or_default())
+                .or_default()
+                .push(hit);
+        }
+        // A store that returned fewer hits than asked for has no more
+        open.retain(|store| fetched.get(store).map_or(0, Vec::len) >= fetch_limit);
+        for (store, hits) in fetched {
+            let hits = hits
+                .into_iter()
+                .filter(|hit| !hit.memory.is_pending() && filters.matches(&hit.memory))
+                .collect();
+            by_store.insert(store, hits);
+        }
+
+        let found: usize = by_store.values().map(Vec::len).sum();
+        if found >= limit || open.is_empty() {
+            let mut hits: Vec<_> = by_store.into_values().flatten().collect();
+            hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+            hits.truncate(limit);
+            return Ok(hits);
+        }
+        fetch_limit = fetch_limit.saturating_mul(4);
+    }
+}
+
+/// Split `filters` into `mmry search` arguments, for the filters the
+/// installed mmry supports, and the filters left to apply here.
+fn push_down(filters: &MemoryFilters) -> (Vec<String>, MemoryFilters) {
+    let mut remaining = filters.clone();
+    if filters.category.is_none() && filters.tags.is_empty() && filters.min_importance.is_none() {
+        return (Vec::new(), remaining);
+    }
+
+    let supported = search_flags();
+    let mut args = Vec::new();
+    if let Some(category) = remaining
+        .category
+        .take_if(|_| supported.contains("--category"))
+    {
+        args.extend(["--category".to_string(), category]);
+    }
+    // mmry may match any of the tags rather than all of them, so tags only
+    // narrow the fetch and are still checked here
+    if supported.contains("--tags") && !filters.tags.is_empty() {
+        args.extend(["--tags".to_string(), filters.tags.join(",")]);
+    } else if supported.contains("--tag") {
+        for tag in &filters.tags {
+            args.extend(["--tag".to_string(), tag.clone()]);
+        }
+    }
+    if let Some(min) = remaining
+        .min_importance
+        .take_if(|_| supported.contains("--min-importance"))
+    {
+        args.extend(["--min-importance".to_string(), min.to_string()]);
+    }
+    (args, remaining)
+}
+
+/// The flags `mmry search` accepts, going by its `--help`.
+fn search_flags() -> HashSet<String> {
+    let Ok(output) = Command::new("mmry").args(["search", "--help"]).output() else {
+        return HashSet::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '=')
+        .filter(|word| word.starts_with("--"))
+        .map(str::to_string)
+        .collect()
+}
+
+fn search_args(options: &SearchOptions) -> Vec<String> {
+    vec![
+        "search".to_string(),
+        options.query.clone(),
+        "--mode".to_string(),
+        options.mode.clone(),
+    ]
+}
+
+/// Run the same search against several stores concurrently.
+///
+/// A store that fails is reported and skipped; the search only fails if
+/// every store does.
+async fn search_stores(stores: &[String], args: &[String]) -> Result<Vec<MemoryHit>> {
+    let mut json_args = args.to_vec();
+    json_args.push("--json".to_string());
+
+    let mut tasks = JoinSet::new();
+    for store in stores {
+        let cmd = mmry_command_in(Some(store.as_str()).filter(|s| !s.is_empty()), &json_args);
+        let store = store.clone();
+        tasks.spawn(async move {
+            let output = tokio::process::Command::from(cmd).output().await;
+            (store, output)
+        });
+    }
+
+    let mut hits = Vec::new();
+    let mut failures = 0;
+    while let Some(joined) = tasks.join_next().await {
+        let (store, output) = joined?;
+        let result = output
+            .context("failed to run mmry - is mmry installed?")
+            .and_then(|output| {
+                if !output.status.success() {
+                    anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+                }
+                parse_hits(&String::from_utf8_lossy(&output.stdout))
+            });
+        match result {
+            Ok(store_hits) => hits.extend(store_hits.into_iter().map(|mut hit| {
+                hit.store = Some(store.clone());
+                hit
+            })),
+            Err(err) => {
+                eprintln!("warning: search in store '{}' failed: {:#}", store, err);
+                failures += 1;
+            }
+        }
+    }
+
+    if failures == stores.len() {
+        anyhow::bail!("memory search failed in every store");
+    }
+    Ok(hits)
+}
+
+/// Parse `mmry search --json` output.
+///
+/// Accepts a bare list or an object wrapping it (`memories`, `results`,
+/// `hits`, `result`), where each entry is either a memory with a `score`
+/// or `{ "memory": {...}, "score": n }`.
+pub fn parse_hits(stdout: &str) -> Result<Vec<MemoryHit>> {
+    let value: serde_json::Value =
+        serde_json::from_str(stdout).context("failed to parse mmry search output")?;
+    let entries = find_list(value).context("unexpected mmry search output")?;
+
+    let mut hits = Vec::new();
+    for entry in entries {
+        let serde_json::Value::Object(mut obj) = entry else {
+            continue;
+        };
+        let score = obj
+            .remove("score")
+            .and_then(|s| s.as_f64())
+            .unwrap_or_default() as f32;
+        let memory = match obj.remove("memory") {
+            Some(memory) => memory,
+            None => serde_json::Value::Object(obj),
+        };
+        hits.push(MemoryHit {
+            store: None,
+            score,
+            memory: serde_json::from_value(memory).context("unexpected memory in search output")?,
+        });
+    }
+    Ok(hits)
+}
+
+fn find_list(value: serde_json::Value) -> Option<Vec<serde_json::Value>> {
+    match value {
+        serde_json::Value::Array(list) => Some(list),
+        serde_json::Value::Object(mut obj) => ["memories", "results", "hits", "result"]
+            .iter()
+            .find_map(|key| obj.remove(*key))
+            .and_then(find_list),
+        _ => None,
+    }
+}
+
+/// All store names known to mmry.
+pub(super) fn list_stores() -> Result<Vec<String>> {
+    let output = Command::new("mmry")
+        .args(["stores", "list", "--json"])
+        .output()
+        .context("failed to run mmry - is mmry installed?")?;
+
+    if output.status.success() {
+        if let Ok(value) = serde_json::from_slice::<serde_json::Value>(&output.stdout) {
+            let names: Vec<String> = find_list(value)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|entry| match entry {
+                    serde_json::Value::String(name) => Some(name),
+                    serde_json::Value::Object(obj) => {
+                        obj.get("name").and_then(|n| n.as_str()).map(String::from)
+                    }
+                    _ => None,
+                })
+                .collect();
+            if !names.is_empty() {
+                return Ok(names);
+            }
+        }
+    }
+
+    // Older mmry versions only print a plain list
+    let output = Command::new("mmry")
+        .args(["stores", "list"])
+        .output()
+        .context("failed to run mmry - is mmry installed?")?;
+    if !output.status.success() {
+        anyhow::bail!("mmry stores list failed");
+    }
+    let names: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim().trim_start_matches(['*', '-']).trim())
+        .filter(|line| !line.is_empty() && !line.ends_with(':'))
+        .filter_map(|line| line.split_whitespace().next().map(String::from))
+        .collect();
+    if names.is_empty() {
+        anyhow::bail!("no mmry stores found");
+    }
+    Ok(names)
+}
+
+fn print_hits(hits: &[MemoryHit]) {
+    if hits.is_empty() {
+        println!("No memories found.");
+        return;
+    }
+
+    for hit in hits {
+        let store = hit
+            .store
+            .as_deref()
+            .map(|s| format!("[{}] ", s))
+            .unwrap_or_default();
+        let category = hit.memory.category.as_deref().unwrap_or("-");
+        let id = hit.memory.id.as_deref().unwrap_or("-");
+        println!(
+            "{score:>5.2} {store}{category} {id} - {content}",
+            score = hit.score,
+            content = text::compact(&hit.memory.content, 160),
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use std::fs;
+use std::io::Write;
+use std::process::Command;
+
+use super::search::list_stores;
+use super::{run_mmry_raw, store_name, temp_path};
+
+#[derive(Subcommand)]
+pub enum StoresCommand {
+    /// List available stores (default)
+    List,
+
+    /// Show the store agntz uses for the current repo
+    Current,
+
+    /// Create a new store
+    Create {
+        /// Store name
+        name: String,
+    },
+
+    /// Rename a store
+    Rename {
+        /// Existing store name
+        from: String,
+        /// New store name
+        to: String,
+        /// Merge into `to` if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Copy all memories from one store into another
+    Copy {
+        /// Source store
+        from: String,
+        /// Target store (created if missing)
+        to: String,
+        /// Merge into `to` if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Delete a store and all its memories
+    Delete {
+        /// Store name
+        name: String,
+        /// Skip confirmation
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+}
+
+pub async fn handle(command: Option<StoresCommand>) -> Result<()> {
+    match command {
+        // Don't use auto-store for listing stores
+        None | Some(StoresCommand::List) => run_mmry_raw(&["stores", "list"]),
+        Some(StoresCommand::Current) => {
+            match store_name()? {
+                Some(store) => println!("{}", store),
+                None => println!("(mmry default store)"),
+            }
+            Ok(())
+        }
+        Some(StoresCommand::Create { name }) => run_mmry_raw(&["init", "--store", &name]),
+        Some(StoresCommand::Rename { from, to, force }) => {
+            copy_store(&from, &to, force)?;
+            run_mmry_raw(&["stores", "delete", &from])?;
+            println!("Renamed store '{}' to '{}'", from, to);
+            Ok(())
+        }
+        Some(StoresCommand::Copy { from, to, force }) => {
+            copy_store(&from, &to, force)?;
+            println!("Copied store '{}' to '{}'", from, to);
+            Ok(())
+        }
+        Some(StoresCommand::Delete { name, yes }) => {
+            if !yes && !confirm(&format!("Delete store '{}' and all its memories?", name))? {
+                println!("Aborted");
+                return Ok(());
+            }
+            run_mmry_raw(&["stores", "delete", &name])
+        }
+    }
+}
+
+/// Export `from` and import the result into `to`, creating `to` first.
+/// An existing `to` is only merged into with `force`.
+fn copy_store(from: &str, to: &str, force: bool) -> Result<()> {
+    if from == to {
+        anyhow::bail!("'{}' is already the store's name", to);
+    }
+    let exists = list_stores()?.iter().any(|name| name == to);
+    if exists && !force {
+        anyhow::bail!(
+            "store '{}' already exists; pass --force to merge '{}' into it",
+            to,
+            from
+        );
+    }
+
+    let temp_json = temp_path("store_copy.json");
+    let temp_path = temp_json.to_string_lossy().to_string();
+
+    run_quiet(&["--store", from, "export", "-o", &temp_path])?;
+    if !exists {
+        run_quiet(&["init", "--store", to])?;
+    }
+    let result = run_quiet(&["--store", to, "import", &temp_path]);
+    fs::remove_file(&temp_json).ok();
+    result
+}
+
+fn run_quiet(args: &[&str]) -> Result<()> {
+    let output = Command::new("mmry")
+        .args(args)
+        .output()
+        .context("failed to run mmry - is mmry installed?")?;
+
+    if !output.status.success() {
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        anyhow::bail!("mmry {} failed", args.join(" "));
+    }
+
+    Ok(())
+}
+
+fn confirm(prompt: &str) -> Result<bool> {
+    print!("{} [y/N] ", prompt);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
@@ -0,0 +1,547 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::formats::{self, MemoryFormat};
+use super::{fetch_memories, import_memories, run_mmry_quiet, store_name, Memory};
+use crate::text;
+
+/// Default location of the repo-committed memory file.
+pub const SHARED_EXPORT: &str = ".memories/export.json";
+
+/// Which side wins when a memory changed in both the file and the store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Prefer {
+    File,
+    Store,
+}
+
+pub struct SyncOptions {
+    pub file: PathBuf,
+    pub check: bool,
+    pub prefer: Option<Prefer>,
+    pub dry_run: bool,
+    /// Only add the file's new memories to the store, leaving the file as is
+    pub pull_only: bool,
+}
+
+/// What to do with a single memory ID.
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    /// Present only in the file: add it to the store
+    AddToStore,
+    /// Present only in the store: add it to the file
+    AddToFile,
+    /// Changed in the file since the last sync
+    UpdateStore,
+    /// Changed in the store since the last sync
+    UpdateFile,
+    /// Removed from the store since the last sync
+    RemoveFromFile,
+    /// Removed from the file since the last sync
+    RemoveFromStore,
+    /// Changed on both sides
+    Conflict,
+}
+
+impl Action {
+    fn label(&self) -> &'static str {
+        match self {
+            Action::AddToStore => "file -> store (new)",
+            Action::AddToFile => "store -> file (new)",
+            Action::UpdateStore => "file -> store (changed)",
+            Action::UpdateFile => "store -> file (changed)",
+            Action::RemoveFromFile => "store -> file (deleted)",
+            Action::RemoveFromStore => "file -> store (deleted)",
+            Action::Conflict => "CONFLICT",
+        }
+    }
+}
+
+/// Content hashes recorded at the last successful sync, per memory ID.
+///
+/// Kept outside the repo because it describes this clone's store, not the
+/// shared file.
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct SyncBase {
+    hashes: BTreeMap<String, String>,
+    /// Store ID for each file ID that mmry imported under a different one
+    #[serde(default)]
+    store_ids: BTreeMap<String, String>,
+}
+
+pub async fn run(options: SyncOptions) -> Result<()> {
+    let file_memories = if options.file.exists() {
+        formats::read(MemoryFormat::Json, &options.file)?
+    } else {
+        Vec::new()
+    };
+    let store_memories = fetch_memories(false)?;
+
+    if options.check {
+        return check(&file_memories, &store_memories, &options.file);
+    }
+
+    let base_path = base_path()?;
+    let base = load_base(&base_path);
+
+    let file_by_id = by_id(&file_memories);
+    let store_ids = pair_ids(&file_memories, &store_memories, &base.store_ids);
+    let store_by_id = by_file_id(&store_memories, &store_ids);
+    let plan = plan(&file_by_id, &store_by_id, &base, options.prefer);
+    // Memories written by hand into the file don't have an ID yet
+    let unidentified = unpaired(&file_memories, &store_memories, &store_ids);
+
+    let mut plan: Vec<(&String, &Action)> = plan.iter().collect();
+    if options.pull_only {
+        plan.retain(|(_, a)| **a == Action::AddToStore);
+    }
+    let changes = plan.iter().filter(|(_, a)| **a != Action::Conflict).count() + unidentified.len();
+    for (id, action) in &plan {
+        let memory = file_by_id.get(*id).or_else(|| store_by_id.get(*id));
+        let preview = memory
+            .map(|m| text::compact(&m.content, 60))
+            .unwrap_or_default();
+        println!("  {:<24} {} {}", action.label(), id, preview);
+    }
+    for memory in &unidentified {
+        println!(
+            "  {:<24} - {}",
+            Action::AddToStore.label(),
+            text::compact(&memory.content, 60)
+        );
+    }
+
+    if plan.is_empty() && unidentified.is_empty() {
+        if !options.pull_only {
+            println!("Memories are in sync ({})", options.file.display());
+        }
+        return Ok(());
+    }
+    if options.dry_run {
+        println!("\nDry run: no changes applied");
+        return Ok(());
+    }
+
+    // Apply store-side changes, importing before removing anything so a
+    // failed import loses nothing
+    let store_id = |id: &String| store_ids.get(id).unwrap_or(id).clone();
+    let mut to_import: Vec<Memory> = unidentified.into_iter().cloned().collect();
+    let mut to_remove = Vec::new();
+    for (id, action) in &plan {
+        match action {
+            Action::AddToStore => to_import.push((*file_by_id[*id]).clone()),
+            Action::UpdateStore => {
+                // Without its ID, so mmry can't keep or merge it into the
+                // record removed below
+                to_import.push(Memory {
+                    id: None,
+                    ..(*file_by_id[*id]).clone()
+                });
+                to_remove.push(store_id(id));
+            }
+            Action::RemoveFromStore => to_remove.push(store_id(id)),
+            _ => {}
+        }
+    }
+    if !to_import.is_empty() {
+        import_memories(&to_import)?;
+    }
+    for id in &to_remove {
+        run_mmry_quiet(&["rm".to_string(), id.clone()])?;
+    }
+
+    if options.pull_only {
+        println!(
+            "Imported {} shared memories; run `agntz memory sync` to reconcile the rest",
+            to_import.len()
+        );
+        return Ok(());
+    }
+
+    // mmry may have given imported memories new IDs, so pair them up again
+    let store_memories = fetch_memories(false)?;
+    let mut known = store_ids.clone();
+    known.retain(|_, sid| !to_remove.contains(sid));
+    let store_ids = pair_ids(&file_memories, &store_memories, &known);
+
+    // The file becomes the store's view, under the IDs the file already
+    // uses, except for unresolved conflicts which keep the file's version
+    // until someone picks a side.
+    let conflicts: BTreeSet<&String> = plan
+        .iter()
+        .filter(|(_, a)| **a == Action::Conflict)
+        .map(|(id, _)| *id)
+        .collect();
+    let mut merged: Vec<Memory> = by_file_id(&store_memories, &store_ids)
+        .into_iter()
+        .filter(|(id, _)| !conflicts.contains(id))
+        .map(|(id, m)| Memory {
+            id: Some(id),
+            ..m.clone()
+        })
+        .collect();
+    merged.extend(store_memories.iter().filter(|m| m.id.is_none()).cloned());
+    merged.extend(conflicts.iter().map(|id| (*file_by_id[*id]).clone()));
+    merged.sort_by(|a, b| (&a.created_at, &a.id).cmp(&(&b.created_at, &b.id)));
+
+    if let Some(parent) = options.file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    formats::write(MemoryFormat::Json, &merged, &options.file)?;
+
+    let mut new_base = SyncBase::default();
+    for memory in &merged {
+        let Some(id) = &memory.id else { continue };
+        let hash = if conflicts.contains(id) {
+            base.hashes.get(id).cloned()
+        } else {
+            Some(content_hash(memory))
+        };
+        if let Some(hash) = hash {
+            new_base.hashes.insert(id.clone(), hash);
+        }
+        if let Some(sid) = store_ids.get(id) {
+            new_base.store_ids.insert(id.clone(), sid.clone());
+        }
+    }
+    save_base(&base_path, &new_base)?;
+
+    println!(
+        "\nSynced {} with the store ({} change(s), {} conflict(s))",
+        options.file.display(),
+        changes,
+        conflicts.len()
+    );
+    if !conflicts.is_empty() {
+        println!("Resolve conflicts with --prefer file|store");
+    }
+    Ok(())
+}
+
+/// Compare file and store without touching either; fail if they differ.
+fn check(file_memories: &[Memory], store_memories: &[Memory], file: &Path) -> Result<()> {
+    let store_ids = pair_ids(file_memories, store_memories, &BTreeMap::new());
+    let file_hashes: BTreeMap<String, String> = by_id(file_memories)
+        .into_iter()
+        .map(|(id, m)| (id, content_hash(m)))
+        .collect();
+    let store_hashes: BTreeMap<String, String> = by_file_id(store_memories, &store_ids)
+        .into_iter()
+        .map(|(id, m)| (id, content_hash(m)))
+        .collect();
+
+    let mut differences = 0;
+    for memory in unpaired(file_memories, store_memories, &store_ids) {
+        println!("  only in file: - {}", text::compact(&memory.content, 60));
+        differences += 1;
+    }
+    let ids: BTreeSet<&String> = file_hashes.keys().chain(store_hashes.keys()).collect();
+    for id in ids {
+        match (file_hashes.get(id), store_hashes.get(id)) {
+            (Some(a), Some(b)) if a == b => continue,
+            (Some(_), Some(_)) => println!("  differs: {}", id),
+            (Some(_), None) => println!("  only in file: {}", id),
+            (None, Some(_)) => println!("  only in store: {}", id),
+            (None, None) => continue,
+        }
+        differences += 1;
+    }
+
+    if differences > 0 {
+        anyhow::bail!(
+            "{} is out of sync with the store ({} difference(s)); run `agntz memory sync`",
+            file.display(),
+            differences
+        );
+    }
+    println!("Memories are in sync ({})", file.display());
+    Ok(())
+}
+
+fn plan(
+    file: &BTreeMap<String, &Memory>,
+    store: &BTreeMap<String, &Memory>,
+    base: &SyncBase,
+    prefer: Option<Prefer>,
+) -> BTreeMap<String, Action> {
+    let mut plan = BTreeMap::new();
+    let ids: BTreeSet<&String> = file.keys().chain(store.keys()).collect();
+
+    for id in ids {
+        let known = base.hashes.get(id);
+        let action = match (file.get(id), store.get(id)) {
+            (Some(f), Some(s)) => {
+                let (fh, sh) = (content_hash(f), content_hash(s));
+                if fh == sh {
+                    continue;
+                }
+                let file_changed = known != Some(&fh);
+                let store_changed = known != Some(&sh);
+                match (file_changed, store_changed, prefer) {
+                    (true, false, _) | (true, true, Some(Prefer::File)) => Action::UpdateStore,
+                    (false, true, _) | (true, true, Some(Prefer::Store)) => Action::UpdateFile,
+                    _ => Action::Conflict,
+                }
+            }
+            (Some(_), None) if known.is_some() => Action::RemoveFromFile,
+            (Some(_), None) => Action::AddToStore,
+            (None, Some(_)) if known.is_some() => Action::RemoveFromStore,
+            (None, Some(_)) => Action::AddToFile,
+            (None, None) => continue,
+        };
+        plan.insert(id.clone(), action);
+    }
+
+    plan
+}
+
+/// Hash of the fields people actually edit; store-managed metadata such as
+/// embeddings or access counts is ignored.
+fn content_hash(memory: &Memory) -> String {
+    let mut tags = memory.tags.clone();
+    tags.sort();
+    let canonical = serde_json::json!({
+        "content": memory.content.trim(),
+        "category": memory.category,
+        "tags": tags,
+        "importance": memory.importance,
+    });
+    let digest = Sha256::digest(canonical.to_string().as_bytes());
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn by_id(memories: &[Memory]) -> BTreeMap<String, &Memory> {
+    memories
+        .iter()
+        .filter_map(|m| m.id.clone().map(|id| (id, m)))
+        .collect()
+}
+
+/// Pair file memories with the store memories they were imported as.
+///
+/// mmry doesn't promise to keep IDs on import, so every clone may know a
+/// shared memory under a different ID. Pairs recorded at the last sync are
+/// kept and the rest are matched on content. Returns the store ID for each
+/// file ID that the store doesn't have itself.
+fn pair_ids(
+    file: &[Memory],
+    store: &[Memory],
+    known: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    let store_by_id = by_id(store);
+    let file_ids: BTreeSet<&String> = file.iter().filter_map(|m| m.id.as_ref()).collect();
+    let mut pairs: BTreeMap<String, String> = known
+        .iter()
+        .filter(|(fid, sid)| {
+            file_ids.contains(fid)
+                && !store_by_id.contains_key(*fid)
+                && store_by_id.contains_key(*sid)
+        })
+        .map(|(fid, sid)| (fid.clone(), sid.clone()))
+        .collect();
+
+    let mut free = unclaimed(&store_by_id, &file_ids, &pairs);
+    for memory in file {
+        let Some(id) = &memory.id else { continue };
+        if store_by_id.contains_key(id) || pairs.contains_key(id) {
+            continue;
+        }
+        if let Some(sid) = free
+            .get_mut(&content_hash(memory))
+            .and_then(|ids| ids.pop())
+        {
+            pairs.insert(id.clone(), sid);
+        }
+    }
+    pairs
+}
+
+/// Store memories keyed by the ID the file knows them by.
+fn by_file_id<'a>(
+    store: &'a [Memory],
+    store_ids: &BTreeMap<String, String>,
+) -> BTreeMap<String, &'a Memory> {
+    let file_ids: BTreeMap<&String, &String> =
+        store_ids.iter().map(|(fid, sid)| (sid, fid)).collect();
+    by_id(store)
+        .into_iter()
+        .map(|(sid, m)| match file_ids.get(&sid) {
+            Some(fid) => ((*fid).clone(), m),
+            None => (sid, m),
+        })
+        .collect()
+}
+
+/// File memories without an ID whose content isn't in the store yet.
+fn unpaired<'a>(
+    file: &'a [Memory],
+    store: &[Memory],
+    store_ids: &BTreeMap<String, String>,
+) -> Vec<&'a Memory> {
+    let file_ids: BTreeSet<&String> = file.iter().filter_map(|m| m.id.as_ref()).collect();
+    let mut free = unclaimed(&by_id(store), &file_ids, store_ids);
+    file.iter()
+        .filter(|m| m.id.is_none())
+        .filter(|m| {
+            free.get_mut(&content_hash(m))
+                .and_then(|ids| ids.pop())
+                .is_none()
+        })
+        .collect()
+}
+
+/// Store IDs not matched to a file memory yet, by content hash.
+fn unclaimed(
+    store: &BTreeMap<String, &Memory>,
+    file_ids: &BTreeSet<&String>,
+    store_ids: &BTreeMap<String, String>,
+) -> BTreeMap<String, Vec<String>> {
+    let mut free: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (sid, memory) in store {
+        if !file_ids.contains(sid) && !store_ids.values().any(|v| v == sid) {
+            free.entry(content_hash(memory))
+                .or_default()
+                .push(sid.clone());
+        }
+    }
+    free
+}
+
+fn base_path() -> Result<PathBuf> {
+    let state = dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .context("could not determine state directory")?;
+    let store = store_name()?.unwrap_or_else(|| "default".to_string());
+    Ok(state
+        .join("agntz")
+        .join("sync")
+        .join(format!("{}.json", store)))
+}
+
+fn load_base(path: &Path) -> SyncBase {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_base(path: &Path, base: &SyncBase) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(base)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory(id: &str, content: &str) -> Memory {
+        Memory {
+            id: (!id.is_empty()).then(|| id.to_string()),
+            content: content.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn base(entries: &[(&str, &Memory)]) -> SyncBase {
+        SyncBase {
+            hashes: entries
+                .iter()
+                .map(|(id, m)| (id.to_string(), content_hash(m)))
+                .collect(),
+            store_ids: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn plan_follows_the_side_that_changed() {
+        let old = memory("a", "old");
+        let new = memory("a", "new");
+        let base = base(&[("a", &old)]);
+
+        let file = by_id(std::slice::from_ref(&new));
+        let store = by_id(std::slice::from_ref(&old));
+        assert_eq!(plan(&file, &store, &base, None)["a"], Action::UpdateStore);
+        assert_eq!(plan(&store, &file, &base, None)["a"], Action::UpdateFile);
+    }
+
+    #[test]
+    fn plan_reports_conflicts_unless_a_side_is_preferred() {
+        let base = base(&[("a", &memory("a", "old"))]);
+        let ours = [memory("a", "ours")];
+        let theirs = [memory("a", "theirs")];
+        let (file, store) = (by_id(&ours), by_id(&theirs));
+
+        assert_eq!(plan(&file, &store, &base, None)["a"], Action::Conflict);
+        assert_eq!(
+            plan(&file, &store, &base, Some(Prefer::File))["a"],
+            Action::UpdateStore
+        );
+        assert_eq!(
+            plan(&file, &store, &base, Some(Prefer::Store))["a"],
+            Action::UpdateFile
+        );
+    }
+
+    #[test]
+    fn plan_tells_new_memories_from_removed_ones() {
+        let known = memory("k", "known");
+        let base = base(&[("k", &known)]);
+        let fresh = memory("f", "fresh");
+        let one = [known.clone()];
+        let other = [fresh.clone()];
+
+        let plan = plan(&by_id(&one), &by_id(&other), &base, None);
+        assert_eq!(plan["k"], Action::RemoveFromFile);
+        assert_eq!(plan["f"], Action::AddToFile);
+
+        let same = [known, fresh];
+        assert!(super::plan(&by_id(&same), &by_id(&same), &base, None).is_empty());
+    }
+
+    #[test]
+    fn pair_ids_matches_reimported_memories_by_content() {
+        let file = [
+            memory("f1", "alpha"),
+            memory("f2", "beta"),
+            memory("s3", "gamma"),
+        ];
+        let store = [
+            memory("s1", "alpha"),
+            memory("s2", "beta"),
+            memory("s3", "gamma"),
+        ];
+
+        let pairs = pair_ids(&file, &store, &BTreeMap::new());
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs["f1"], "s1");
+        assert_eq!(pairs["f2"], "s2");
+        assert_eq!(by_file_id(&store, &pairs).len(), 3);
+    }
+
+    #[test]
+    fn pair_ids_keeps_known_pairs_after_edits() {
+        let file = [memory("f1", "alpha")];
+        let store = [memory("s1", "alpha, edited"), memory("s2", "alpha")];
+        let known = BTreeMap::from([("f1".to_string(), "s1".to_string())]);
+
+        assert_eq!(pair_ids(&file, &store, &known)["f1"], "s1");
+        // A pair whose store memory is gone is matched on content again
+        let store = [memory("s2", "alpha")];
+        assert_eq!(pair_ids(&file, &store, &known)["f1"], "s2");
+    }
+
+    #[test]
+    fn unpaired_skips_content_the_store_has() {
+        let file = [memory("", "alpha"), memory("", "beta")];
+        let store = [memory("s1", "alpha")];
+        let missing = unpaired(&file, &store, &BTreeMap::new());
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].content, "beta");
+    }
+}
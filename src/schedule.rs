@@ -2,6 +2,11 @@ use anyhow::{Context, Result};
 use clap::Subcommand;
 use std::process::Command;
 
+use crate::{activity, agent};
+
+/// skdlr subcommands that change schedules, and so need attributing.
+const SKDLR_WRITES: &[&str] = &["add", "edit", "remove", "enable", "disable", "run"];
+
 #[derive(Subcommand)]
 pub enum ScheduleCommand {
     /// Add a new scheduled task
@@ -281,6 +286,7 @@ fn run_skdlr(args: &[String]) -> Result<()> {
         .or_else(|| std::env::var("SKDLR_CONFIG").ok());
 
     let mut command = Command::new(skdlr_bin);
+    let unrecorded = agent::attribute("skdlr", &mut command);
 
     if let Some(config_path) = skdlr_config {
         command.arg("--config").arg(config_path);
@@ -291,6 +297,15 @@ fn run_skdlr(args: &[String]) -> Result<()> {
         .output()
         .context("failed to run skdlr - is skdlr installed?")?;
 
+    if let Some(attribution) = unrecorded {
+        if args
+            .first()
+            .is_some_and(|a| SKDLR_WRITES.contains(&a.as_str()))
+        {
+            activity::record_backend("skdlr", args, attribution, output.status.success());
+        }
+    }
+
     print!("{}", String::from_utf8_lossy(&output.stdout));
     if !output.stderr.is_empty() {
         eprint!("{}", String::from_utf8_lossy(&output.stderr));
@@ -0,0 +1,960 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use clap::{Args, Subcommand, ValueEnum};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+use crate::hstry::{self, Conversation, Message};
+use crate::text;
+
+mod export;
+mod promote;
+mod saved;
+mod workspace;
+
+use export::ExportFormat;
+use promote::PromoteTarget;
+pub use saved::SavedSearch;
+use workspace::WorkspaceScope;
+
+#[derive(Subcommand)]
+pub enum SearchCommand {
+    /// Show a whole conversation, e.g. to read around a search hit
+    Show {
+        /// Session or conversation ID, as printed by search
+        id: String,
+        /// First message index to show
+        #[arg(long, value_name = "IDX")]
+        from: Option<i32>,
+        /// Last message index to show
+        #[arg(long, value_name = "IDX")]
+        to: Option<i32>,
+        /// Output format
+        #[arg(long, value_enum, default_value = "md")]
+        format: ShowFormat,
+    },
+
+    /// Turn a message into a memory or a task, noting where it came from
+    Promote {
+        /// Message ID from the last search, or <session>#<idx>
+        message: String,
+        /// What to make of it
+        #[arg(long, value_enum)]
+        to: PromoteTarget,
+        /// Task title (defaults to the message's first line)
+        #[arg(long)]
+        title: Option<String>,
+        /// Memory category
+        #[arg(short, long)]
+        category: Option<String>,
+    },
+
+    /// Save a search under a name, with all its options
+    Save {
+        /// Name to run it by
+        name: String,
+        /// Also run it on this cron schedule via skdlr, reporting new hits
+        #[arg(long, value_name = "CRON")]
+        schedule: Option<String>,
+        #[command(flatten)]
+        args: Box<SearchArgs>,
+    },
+
+    /// Run a saved search
+    Run {
+        /// Saved search name
+        name: String,
+        /// Only hits not reported by an earlier `run --new`
+        #[arg(long)]
+        new: bool,
+    },
+
+    /// List saved searches
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ShowFormat {
+    #[value(alias = "markdown")]
+    Md,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Best matches first
+    Score,
+    /// Newest first
+    Date,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    Session,
+    Workspace,
+    /// Day of the message, in local time
+    Day,
+}
+
+const DEFAULT_LIMIT: usize = 20;
+
+/// Matches looked at when sorting by date, grouping or counting facets.
+const POOL_SIZE: usize = 500;
+
+/// Options for `agntz search`, also the form saved searches are kept in.
+#[derive(Args, Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SearchArgs {
+    /// Search query (put `--` before it if it is a subcommand name, e.g. `-- show`)
+    #[arg(required = true)]
+    pub(crate) query: Option<String>,
+    /// Limit to specific workspace path (defaults to current repo/dir)
+    #[arg(short, long, alias = "repo")]
+    workspace: Option<String>,
+    /// Limit to last N days
+    #[arg(long)]
+    days: Option<u32>,
+    /// Limit to a specific session/conversation ID
+    #[arg(long)]
+    session: Option<String>,
+    /// Maximum results to return
+    #[arg(short, long, default_value_t = DEFAULT_LIMIT)]
+    limit: usize,
+    /// Search all workspaces (disables default workspace filter)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    all_workspaces: bool,
+    /// Only sessions run in this subdirectory of the repo (e.g. `.` in a monorepo package)
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["workspace", "all_workspaces"])]
+    scope: Option<PathBuf>,
+    /// Also sessions run in subdirectories (and submodules) of the repo; slower
+    #[arg(long, conflicts_with_all = ["workspace", "all_workspaces"])]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    subdirs: bool,
+    /// Include tool calls/results
+    #[arg(long)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    include_tools: bool,
+    /// Include system context (AGENTS.md, etc.)
+    #[arg(long)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    include_system: bool,
+    /// Disable result deduplication
+    #[arg(long)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    no_dedup: bool,
+    /// Only messages with this role (repeatable)
+    #[arg(long = "role", value_parser = ["user", "assistant", "tool", "system"])]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    roles: Vec<String>,
+    /// Only hits from this hstry source
+    #[arg(long)]
+    source: Option<String>,
+    /// Only hits imported by this adapter (e.g. claude-code, codex)
+    #[arg(long)]
+    adapter: Option<String>,
+    /// Only hits from this host
+    #[arg(long)]
+    host: Option<String>,
+    /// Only conversations whose title contains this text
+    #[arg(long)]
+    title: Option<String>,
+    /// Order of the results
+    #[arg(long, value_enum, default_value = "score")]
+    sort: SortOrder,
+    /// Show N messages before and after each hit in its conversation
+    #[arg(short = 'C', long, value_name = "N")]
+    context: Option<usize>,
+    /// Print whole messages instead of snippets
+    #[arg(long)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    full: bool,
+    /// Collapse hits into groups, with counts and the best hit of each
+    #[arg(long, value_enum, conflicts_with_all = ["context", "full"])]
+    group_by: Option<GroupBy>,
+    /// Only count hits per workspace, source and role
+    #[arg(long, conflicts_with_all = ["context", "full", "group_by"])]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    facets: bool,
+    /// Output raw JSON results
+    #[arg(long)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    json: bool,
+    /// Write the results to this file (format from its extension)
+    #[arg(short, long, value_name = "FILE", conflicts_with_all = ["group_by", "facets", "json"])]
+    output: Option<PathBuf>,
+    /// Format for --output (or stdout): md, jsonl or csv
+    #[arg(long, value_enum, conflicts_with_all = ["group_by", "facets", "json"])]
+    format: Option<ExportFormat>,
+}
+
+impl Default for SearchArgs {
+    fn default() -> Self {
+        SearchArgs {
+            query: None,
+            workspace: None,
+            days: None,
+            session: None,
+            limit: DEFAULT_LIMIT,
+            all_workspaces: false,
+            scope: None,
+            subdirs: false,
+            include_tools: false,
+            include_system: false,
+            no_dedup: false,
+            roles: Vec::new(),
+            source: None,
+            adapter: None,
+            host: None,
+            title: None,
+            sort: SortOrder::Score,
+            context: None,
+            full: false,
+            group_by: None,
+            facets: false,
+            json: false,
+            output: None,
+            format: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct HstrySearchHit {
+    pub message_id: String,
+    pub conversation_id: String,
+    pub message_idx: i32,
+    pub role: String,
+    pub content: String,
+    pub snippet: String,
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub conv_created_at: chrono::DateTime<chrono::Utc>,
+    pub conv_updated_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub score: f32,
+    pub source_id: String,
+    pub external_id: Option<String>,
+    pub title: Option<String>,
+    pub workspace: Option<String>,
+    pub source_adapter: String,
+    pub source_path: Option<String>,
+    pub host: Option<String>,
+}
+
+impl HstrySearchHit {
+    fn timestamp(&self) -> chrono::DateTime<chrono::Utc> {
+        self.created_at
+            .or(self.conv_updated_at)
+            .unwrap_or(self.conv_created_at)
+    }
+}
+
+/// Constraints checked here rather than by hstry.
+#[derive(Default)]
+struct HitFilter<'a> {
+    workspace: Option<&'a WorkspaceScope>,
+    /// Message IDs to leave out
+    seen: Option<&'a HashSet<String>>,
+    session: Option<&'a str>,
+    cutoff: Option<chrono::DateTime<chrono::Utc>>,
+    roles: &'a [String],
+    source: Option<&'a str>,
+    adapter: Option<&'a str>,
+    host: Option<&'a str>,
+    title: Option<String>,
+}
+
+impl HitFilter<'_> {
+    fn is_empty(&self) -> bool {
+        self.workspace.is_none()
+            && self.seen.is_none()
+            && self.session.is_none()
+            && self.cutoff.is_none()
+            && self.roles.is_empty()
+            && self.source.is_none()
+            && self.adapter.is_none()
+            && self.host.is_none()
+            && self.title.is_none()
+    }
+
+    fn matches(&self, hit: &HstrySearchHit) -> bool {
+        let same = |wanted: Option<&str>, value: Option<&str>| match wanted {
+            Some(wanted) => value.is_some_and(|v| v.eq_ignore_ascii_case(wanted)),
+            None => true,
+        };
+
+        self.seen.is_none_or(|seen| !seen.contains(&hit.message_id))
+            && self
+                .workspace
+                .is_none_or(|scope| hit.workspace.as_deref().is_some_and(|w| scope.matches(w)))
+            && self.session.is_none_or(|id| {
+                hstry::matches_id(
+                    id,
+                    &hit.conversation_id,
+                    hit.external_id.as_deref(),
+                    hit.source_path.as_deref(),
+                )
+            })
+            && self.cutoff.is_none_or(|cutoff| hit.timestamp() >= cutoff)
+            && (self.roles.is_empty() || self.roles.contains(&hit.role))
+            && same(self.source, Some(&hit.source_id))
+            && same(self.adapter, Some(&hit.source_adapter))
+            && same(self.host, hit.host.as_deref())
+            && self.title.as_ref().is_none_or(|title| {
+                hit.title
+                    .as_deref()
+                    .is_some_and(|t| t.to_lowercase().contains(title))
+            })
+    }
+}
+
+/// A hit with the surrounding messages of its conversation.
+#[derive(serde::Serialize)]
+struct HitWithContext {
+    #[serde(flatten)]
+    hit: HstrySearchHit,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    context: Vec<Message>,
+}
+
+pub async fn handle(command: Option<SearchCommand>, args: Box<SearchArgs>) -> Result<()> {
+    match command {
+        Some(SearchCommand::Show {
+            id,
+            from,
+            to,
+            format,
+        }) => show(&id, from, to, format),
+        Some(SearchCommand::Promote {
+            message,
+            to,
+            title,
+            category,
+        }) => promote::run(&message, to, title, category).await,
+        Some(SearchCommand::Save {
+            name,
+            schedule,
+            args,
+        }) => saved::save(name, *args, schedule).await,
+        Some(SearchCommand::Run { name, new }) => saved::run(&name, new),
+        Some(SearchCommand::List { json }) => saved::list(json),
+        None => run(*args, None),
+    }
+}
+
+/// Run a search; with `only_new`, a saved search's name, hits it already
+/// reported are left out.
+fn run(args: SearchArgs, only_new: Option<&str>) -> Result<()> {
+    let query = args.query.clone().unwrap_or_default();
+    let mut hstry_args = vec!["search".to_string(), query.clone()];
+
+    // An explicit workspace is matched by hstry; the default one covers every
+    // checkout of the repo, which only we can tell
+    let scope = if let Some(workspace) = &args.workspace {
+        hstry_args.push("--workspace".to_string());
+        hstry_args.push(workspace.clone());
+        None
+    } else if args.all_workspaces {
+        None
+    } else {
+        Some(WorkspaceScope::current(
+            args.scope.as_deref(),
+            args.subdirs,
+        )?)
+    };
+
+    let dedup = !args.no_dedup;
+    if dedup {
+        hstry_args.push("--dedup".to_string());
+    }
+    // Asking for tool messages by role implies including them
+    if !args.include_tools && !args.roles.iter().any(|r| r == "tool") {
+        hstry_args.push("--no-tools".to_string());
+    }
+    if args.include_system {
+        hstry_args.push("--include-system".to_string());
+    }
+
+    // Whatever hstry can't filter itself is filtered here, so keep fetching
+    // until there are enough hits or nothing more to fetch
+    let by_date = args.sort == SortOrder::Date;
+    let supported = if args.session.is_some() || args.days.is_some() || by_date {
+        hstry::flags("search")
+    } else {
+        Default::default()
+    };
+    let mut session = args.session.as_deref();
+    if let Some(id) = session.filter(|_| supported.contains("--session")) {
+        hstry_args.push("--session".to_string());
+        hstry_args.push(id.to_string());
+        session = None;
+    }
+    let mut days = args.days;
+    if let Some(d) = days.filter(|_| supported.contains("--days")) {
+        hstry_args.push("--days".to_string());
+        hstry_args.push(d.to_string());
+        days = None;
+    }
+    let dated_by_hstry = by_date && supported.contains("--sort");
+    if dated_by_hstry {
+        hstry_args.push("--sort".to_string());
+        hstry_args.push("date".to_string());
+    }
+
+    // The newest hits, groups and facets need more than the best `--limit`
+    // matches to be right
+    let pooled = args.group_by.is_some() || args.facets;
+    let wanted = if (by_date && !dated_by_hstry) || pooled {
+        args.limit.max(POOL_SIZE)
+    } else {
+        args.limit
+    };
+
+    let seen = only_new.map(saved::seen_ids).transpose()?;
+    let filter = HitFilter {
+        workspace: None,
+        seen: seen.as_ref(),
+        session,
+        // A range reaching past the earliest representable date filters nothing
+        cutoff: days.and_then(|d| {
+            chrono::Utc::now().checked_sub_signed(chrono::TimeDelta::try_days(i64::from(d))?)
+        }),
+        roles: &args.roles,
+        source: args.source.as_deref(),
+        adapter: args.adapter.as_deref(),
+        host: args.host.as_deref(),
+        title: args.title.as_ref().map(|t| t.to_lowercase()),
+    };
+    let (mut hits, complete) = collect_hits(&hstry_args, wanted, filter, scope.as_ref())?;
+    if by_date {
+        if !dated_by_hstry && !complete {
+            eprintln!(
+                "Note: only the best {} matches were sorted by date (this hstry has no --sort); newer ones may be missing",
+                hits.len()
+            );
+        }
+        hits.sort_by_key(|hit| std::cmp::Reverse(hit.timestamp()));
+    }
+    if !pooled {
+        hits.truncate(args.limit);
+    }
+    // Counts over a pool that didn't hold every match are lower bounds
+    let approximate = pooled && !complete;
+    if let Some(name) = only_new {
+        saved::mark_seen(name, &hits)?;
+        if hits.is_empty() && !args.json {
+            println!("No new results.");
+            return Ok(());
+        }
+    }
+    promote::remember(&hits);
+
+    let highlighter = Highlighter::new(&query);
+    if args.facets {
+        let facets = facets(&hits);
+        if args.json {
+            let payload = serde_json::json!({ "facets": facets, "approximate": approximate });
+            println!("{}", serde_json::to_string_pretty(&payload)?);
+        } else {
+            print_facets(&facets, approximate);
+        }
+        return Ok(());
+    }
+    if let Some(by) = args.group_by {
+        let mut groups = group_hits(hits, by);
+        groups.truncate(args.limit);
+        if args.json {
+            let payload = serde_json::json!({ "groups": groups, "approximate": approximate });
+            println!("{}", serde_json::to_string_pretty(&payload)?);
+        } else {
+            print_groups(&groups, approximate, &highlighter);
+        }
+        return Ok(());
+    }
+
+    let hits = with_context(hits, args.context.unwrap_or(0));
+
+    let export_format = match (args.format, &args.output) {
+        (Some(format), _) => Some(format),
+        (None, Some(path)) => Some(ExportFormat::detect(path)?),
+        (None, None) => None,
+    };
+    if let Some(format) = export_format {
+        return export::export(&hits, &query, format, args.output.as_deref());
+    }
+
+    if args.json {
+        let payload = serde_json::json!({ "hits": hits });
+        println!("{}", serde_json::to_string_pretty(&payload)?);
+        return Ok(());
+    }
+
+    if args.context.is_some() || args.full {
+        print_detailed_hits(&hits, args.full, &highlighter);
+    } else {
+        print_compact_hits(&hits, &highlighter);
+    }
+    Ok(())
+}
+
+/// Search session history in the current workspace (or all of them), for
+/// callers that merge hits with other results.
+pub fn history(query: &str, limit: usize, all_workspaces: bool) -> Result<Vec<HstrySearchHit>> {
+    let args = [
+        "search".to_string(),
+        query.to_string(),
+        "--dedup".to_string(),
+        "--no-tools".to_string(),
+    ];
+    let scope = match all_workspaces {
+        true => None,
+        false => Some(WorkspaceScope::current(None, false)?),
+    };
+    let (mut hits, _) = collect_hits(&args, limit, HitFilter::default(), scope.as_ref())?;
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+/// At least `limit` hits in `scope` (or anywhere without one) if there are
+/// that many, best first, and whether they are all there are.
+///
+/// hstry matches workspaces exactly, so each worktree is searched on its own.
+/// Only sessions in subdirectories and in other clones are matched here.
+fn collect_hits(
+    hstry_args: &[String],
+    limit: usize,
+    filter: HitFilter,
+    scope: Option<&WorkspaceScope>,
+) -> Result<(Vec<HstrySearchHit>, bool)> {
+    let Some(scope) = scope.filter(|scope| !scope.nested()) else {
+        let filter = HitFilter {
+            workspace: scope,
+            ..filter
+        };
+        return fetch_hits(hstry_args, limit, &filter);
+    };
+
+    let mut hits = Vec::new();
+    let mut complete = true;
+    for workspace in scope.workspaces() {
+        let mut args = hstry_args.to_vec();
+        args.push("--workspace".to_string());
+        args.push(workspace);
+        let (found, exhausted) = fetch_hits(&args, limit, &filter)?;
+        hits.extend(found);
+        complete &= exhausted;
+    }
+
+    // Other clones can't be named to hstry, so when the repo's own sessions
+    // don't fill the results, look for them among the best matches anywhere
+    if scope.has_clones() && hits.len() < limit {
+        let fetch_limit = limit.saturating_mul(4);
+        let mut args = hstry_args.to_vec();
+        args.push("--limit".to_string());
+        args.push(fetch_limit.to_string());
+        let batch: Vec<HstrySearchHit> = hstry::run_json(&args)?.unwrap_or_default();
+        complete &= batch.len() < fetch_limit;
+        hits.extend(batch.into_iter().filter(|hit| {
+            filter.matches(hit) && hit.workspace.as_deref().is_some_and(|w| scope.is_clone(w))
+        }));
+    } else if scope.has_clones() {
+        complete = false;
+    }
+
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok((hits, complete))
+}
+
+/// Fetch hits with growing limits until `limit` of them pass the local
+/// filters or hstry returns fewer than asked for. Also returns whether hstry
+/// ran out of matches, i.e. the hits are all there are.
+fn fetch_hits(
+    hstry_args: &[String],
+    limit: usize,
+    filter: &HitFilter,
+) -> Result<(Vec<HstrySearchHit>, bool)> {
+    let mut fetch_limit = limit.max(1);
+    loop {
+        let mut args = hstry_args.to_vec();
+        args.push("--limit".to_string());
+        args.push(fetch_limit.to_string());
+
+        let batch: Vec<HstrySearchHit> = hstry::run_json(&args)?.unwrap_or_default();
+        let exhausted = batch.len() < fetch_limit;
+        let hits: Vec<_> = batch.into_iter().filter(|h| filter.matches(h)).collect();
+        if hits.len() >= limit || exhausted || filter.is_empty() {
+            return Ok((hits, exhausted));
+        }
+        fetch_limit = fetch_limit.saturating_mul(4);
+    }
+}
+
+/// Attach the `n` messages before and after each hit, fetching each
+/// conversation from hstry once.
+fn with_context(hits: Vec<HstrySearchHit>, n: usize) -> Vec<HitWithContext> {
+    let mut conversations: HashMap<String, Vec<Message>> = HashMap::new();
+
+    hits.into_iter()
+        .map(|hit| {
+            if n == 0 {
+                return HitWithContext {
+                    hit,
+                    context: Vec::new(),
+                };
+            }
+            let messages = conversations
+                .entry(hit.conversation_id.clone())
+                .or_insert_with(|| match hstry::messages(&hit.conversation_id) {
+                    Ok(messages) => messages,
+                    Err(err) => {
+                        eprintln!("warning: no context for {}: {:#}", hit.conversation_id, err);
+                        Vec::new()
+                    }
+                });
+            let from = hit.message_idx.saturating_sub(n as i32);
+            let to = hit.message_idx.saturating_add(n as i32);
+            let context = messages
+                .iter()
+                .filter(|m| m.idx >= from && m.idx <= to)
+                .cloned()
+                .collect();
+            HitWithContext { hit, context }
+        })
+        .collect()
+}
+
+fn show(id: &str, from: Option<i32>, to: Option<i32>, format: ShowFormat) -> Result<()> {
+    let mut conversation =
+        hstry::find(id).with_context(|| format!("could not load conversation {}", id))?;
+    conversation.messages.retain(|m| {
+        from.map(|from| m.idx >= from).unwrap_or(true) && to.map(|to| m.idx <= to).unwrap_or(true)
+    });
+
+    match format {
+        ShowFormat::Json => println!("{}", serde_json::to_string_pretty(&conversation)?),
+        ShowFormat::Md => print!("{}", render_markdown(&conversation)),
+    }
+    Ok(())
+}
+
+fn render_markdown(conversation: &Conversation) -> String {
+    let mut out = format!(
+        "# {}\n\n",
+        conversation.title.as_deref().unwrap_or("Untitled")
+    );
+
+    let mut fields = vec![("Conversation", conversation.id.clone())];
+    if let Some(session) = &conversation.external_id {
+        fields.push(("Session", session.clone()));
+    }
+    if let Some(workspace) = &conversation.workspace {
+        fields.push(("Workspace", workspace.clone()));
+    }
+    match (&conversation.source_id, &conversation.source_adapter) {
+        (Some(source), Some(adapter)) if source != adapter => {
+            fields.push(("Source", format!("{} ({})", source, adapter)))
+        }
+        (Some(source), _) | (None, Some(source)) => fields.push(("Source", source.clone())),
+        (None, None) => {}
+    }
+    if let Some(host) = &conversation.host {
+        fields.push(("Host", host.clone()));
+    }
+    for (name, value) in fields {
+        out.push_str(&format!("- {}: {}\n", name, value));
+    }
+
+    if conversation.messages.is_empty() {
+        out.push_str("\nNo messages in this range.\n");
+    }
+    for message in &conversation.messages {
+        out.push_str(&format!("\n## #{} {}", message.idx, message.role));
+        if let Some(created_at) = message.created_at {
+            out.push_str(&format!(
+                " ({})",
+                created_at.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+            ));
+        }
+        out.push_str(&format!("\n\n{}\n", message.content.trim()));
+    }
+    out
+}
+
+/// Hits sharing a session, workspace or day.
+#[derive(serde::Serialize)]
+struct Group {
+    key: String,
+    count: usize,
+    best: HstrySearchHit,
+}
+
+/// Groups keep the order of their best hits, except days, which go newest
+/// first.
+fn group_hits(hits: Vec<HstrySearchHit>, by: GroupBy) -> Vec<Group> {
+    let mut groups: Vec<Group> = Vec::new();
+    for hit in hits {
+        let key = match by {
+            GroupBy::Session => hit
+                .external_id
+                .clone()
+                .unwrap_or_else(|| hit.conversation_id.clone()),
+            GroupBy::Workspace => hit.workspace.clone().unwrap_or_else(|| "-".to_string()),
+            GroupBy::Day => hit
+                .timestamp()
+                .with_timezone(&Local)
+                .format("%Y-%m-%d")
+                .to_string(),
+        };
+        match groups.iter_mut().find(|g| g.key == key) {
+            Some(group) => {
+                group.count += 1;
+                if hit.score > group.best.score {
+                    group.best = hit;
+                }
+            }
+            None => groups.push(Group {
+                key,
+                count: 1,
+                best: hit,
+            }),
+        }
+    }
+
+    groups.sort_by(|a, b| b.best.score.total_cmp(&a.best.score));
+    if matches!(by, GroupBy::Day) {
+        groups.sort_by(|a, b| b.key.cmp(&a.key));
+    }
+    groups
+}
+
+/// `approximate` marks counts taken from a pool that didn't hold every match,
+/// e.g. `12+ hits`.
+fn print_groups(groups: &[Group], approximate: bool, highlighter: &Highlighter) {
+    if groups.is_empty() {
+        println!("No results found.");
+        return;
+    }
+
+    for group in groups {
+        let noun = if group.count == 1 && !approximate {
+            "hit"
+        } else {
+            "hits"
+        };
+        println!(
+            "{} ({}{} {})",
+            group.key,
+            group.count,
+            if approximate { "+" } else { "" },
+            noun
+        );
+        println!("  {}", compact_line(&group.best, highlighter));
+    }
+}
+
+/// Hit counts per workspace, source and role, largest first.
+#[derive(serde::Serialize)]
+struct Facets {
+    workspace: Vec<FacetCount>,
+    source: Vec<FacetCount>,
+    role: Vec<FacetCount>,
+}
+
+#[derive(serde::Serialize)]
+struct FacetCount {
+    value: String,
+    count: usize,
+}
+
+fn facets(hits: &[HstrySearchHit]) -> Facets {
+    fn count<'a>(values: impl Iterator<Item = &'a str>) -> Vec<FacetCount> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for value in values {
+            *counts.entry(value).or_insert(0) += 1;
+        }
+        let mut counts: Vec<FacetCount> = counts
+            .into_iter()
+            .map(|(value, count)| FacetCount {
+                value: value.to_string(),
+                count,
+            })
+            .collect();
+        counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        counts
+    }
+
+    Facets {
+        workspace: count(hits.iter().map(|h| h.workspace.as_deref().unwrap_or("-"))),
+        source: count(hits.iter().map(|h| h.source_id.as_str())),
+        role: count(hits.iter().map(|h| h.role.as_str())),
+    }
+}
+
+fn print_facets(facets: &Facets, approximate: bool) {
+    if facets.role.is_empty() {
+        println!("No results found.");
+        return;
+    }
+
+    let sections = [
+        ("Workspaces", &facets.workspace),
+        ("Sources", &facets.source),
+        ("Roles", &facets.role),
+    ];
+    for (i, (title, counts)) in sections.into_iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{}:", title);
+        for facet in counts {
+            let count = format!("{}{}", facet.count, if approximate { "+" } else { "" });
+            println!("  {:>5} {}", count, facet.value);
+        }
+    }
+}
+
+fn print_compact_hits(hits: &[HitWithContext], highlighter: &Highlighter) {
+    if hits.is_empty() {
+        println!("No results found.");
+        return;
+    }
+
+    for HitWithContext { hit, .. } in hits {
+        println!("{}", compact_line(hit, highlighter));
+    }
+}
+
+fn compact_line(hit: &HstrySearchHit, highlighter: &Highlighter) -> String {
+    let snippet = text::compact(&hit.snippet, 160);
+    format!(
+        "{} - {}",
+        header(hit, highlighter),
+        highlighter.apply(&snippet)
+    )
+}
+
+fn print_detailed_hits(hits: &[HitWithContext], full: bool, highlighter: &Highlighter) {
+    if hits.is_empty() {
+        println!("No results found.");
+        return;
+    }
+
+    for (i, HitWithContext { hit, context }) in hits.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{}", header(hit, highlighter));
+
+        let hit_text = if full {
+            hit.content.clone()
+        } else {
+            text::compact(&hit.snippet, 160)
+        };
+
+        // Without context (or if it couldn't be fetched), just the hit itself
+        if !context.iter().any(|m| m.idx == hit.message_idx) {
+            print_message(">", hit.message_idx, &hit.role, &hit_text, highlighter);
+            continue;
+        }
+
+        for message in context {
+            if message.idx == hit.message_idx {
+                print_message(">", message.idx, &message.role, &hit_text, highlighter);
+            } else {
+                let text = if full {
+                    message.content.clone()
+                } else {
+                    text::compact(&message.content, 160)
+                };
+                print_message(" ", message.idx, &message.role, &text, highlighter);
+            }
+        }
+    }
+}
+
+fn print_message(marker: &str, idx: i32, role: &str, text: &str, highlighter: &Highlighter) {
+    let mut lines = text.trim().lines();
+    let first = lines.next().unwrap_or("");
+    println!(
+        "  {} #{} {}: {}",
+        marker,
+        idx,
+        role,
+        highlighter.apply(first)
+    );
+    for line in lines {
+        println!("        {}", highlighter.apply(line));
+    }
+}
+
+/// `score source role session #idx workspace title`
+fn header(hit: &HstrySearchHit, highlighter: &Highlighter) -> String {
+    let session_id = hit
+        .external_id
+        .as_deref()
+        .unwrap_or(hit.conversation_id.as_str());
+    let title = text::truncate(hit.title.as_deref().unwrap_or("Untitled"), 40);
+    let workspace = hit
+        .workspace
+        .as_deref()
+        .and_then(|w| w.split('/').next_back())
+        .unwrap_or("-");
+
+    format!(
+        "{score:>5.2} {source} {role} {session} #{idx} {workspace} {title}",
+        score = hit.score,
+        source = hit.source_id,
+        role = hit.role,
+        session = session_id,
+        idx = hit.message_idx,
+        workspace = workspace,
+        title = highlighter.apply(&title)
+    )
+}
+
+/// Marks query terms in output, when it goes to a terminal.
+struct Highlighter {
+    pattern: Option<Regex>,
+}
+
+impl Highlighter {
+    fn new(query: &str) -> Highlighter {
+        let enabled = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+        if !enabled {
+            return Highlighter { pattern: None };
+        }
+
+        // Search syntax aside, every word of the query is worth marking
+        let terms: Vec<String> = query
+            .split_whitespace()
+            .filter(|t| !matches!(*t, "AND" | "OR" | "NOT"))
+            .map(|t| t.trim_matches(|c: char| !c.is_alphanumeric() && c != '_'))
+            .filter(|t| t.chars().count() >= 2)
+            .map(regex::escape)
+            .collect();
+        if terms.is_empty() {
+            return Highlighter { pattern: None };
+        }
+
+        Highlighter {
+            pattern: Regex::new(&format!("(?i){}", terms.join("|"))).ok(),
+        }
+    }
+
+    fn apply(&self, text: &str) -> String {
+        match &self.pattern {
+            Some(pattern) => pattern
+                .replace_all(text, "\x1b[1;33m$0\x1b[0m")
+                .into_owned(),
+            None => text.to_string(),
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use super::{HitWithContext, HstrySearchHit};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// Markdown report, one section per hit
+    #[value(alias = "markdown")]
+    Md,
+    /// One JSON hit per line
+    Jsonl,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+impl ExportFormat {
+    /// Guess the format from the output file's extension.
+    pub fn detect(path: &Path) -> Result<ExportFormat> {
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "md" | "markdown" => Ok(ExportFormat::Md),
+            "jsonl" | "ndjson" => Ok(ExportFormat::Jsonl),
+            "csv" => Ok(ExportFormat::Csv),
+            _ => anyhow::bail!(
+                "can't tell the format of {} from its extension; pass --format md|jsonl|csv",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Write `hits` to `output`, or to stdout without one.
+pub fn export(
+    hits: &[HitWithContext],
+    query: &str,
+    format: ExportFormat,
+    output: Option<&Path>,
+) -> Result<()> {
+    let content = match format {
+        ExportFormat::Md => render_markdown(hits, query),
+        ExportFormat::Jsonl => render_jsonl(hits)?,
+        ExportFormat::Csv => render_csv(hits)?,
+    };
+
+    match output {
+        Some(path) => {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, content)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+            println!("Wrote {} hits to {}", hits.len(), path.display());
+        }
+        None => print!("{}", content),
+    }
+    Ok(())
+}
+
+fn render_markdown(hits: &[HitWithContext], query: &str) -> String {
+    let mut out = format!("# Search: {}\n\n", query);
+    out.push_str(&format!(
+        "{} hits, {}\n",
+        hits.len(),
+        Local::now().format("%Y-%m-%d %H:%M")
+    ));
+
+    for (i, HitWithContext { hit, context }) in hits.iter().enumerate() {
+        out.push_str(&format!(
+            "\n## {}. {} #{}\n\n",
+            i + 1,
+            session_id(hit),
+            hit.message_idx
+        ));
+
+        let mut fields = vec![
+            (
+                "Title",
+                hit.title.clone().unwrap_or_else(|| "Untitled".to_string()),
+            ),
+            ("Role", hit.role.clone()),
+            ("Score", format!("{:.2}", hit.score)),
+            (
+                "Date",
+                hit.timestamp()
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string(),
+            ),
+            (
+                "Source",
+                format!("{} ({})", hit.source_id, hit.source_adapter),
+            ),
+        ];
+        if let Some(workspace) = &hit.workspace {
+            fields.push(("Workspace", workspace.clone()));
+        }
+        if let Some(path) = &hit.source_path {
+            fields.push(("Transcript", format!("[{}](file://{})", path, path)));
+        }
+        fields.push(("Open", format!("`{}`", show_command(hit))));
+        for (name, value) in fields {
+            out.push_str(&format!("- {}: {}\n", name, value));
+        }
+
+        out.push('\n');
+        if context.is_empty() {
+            out.push_str(&quote(&hit.content));
+            continue;
+        }
+        for (j, message) in context.iter().enumerate() {
+            if j > 0 {
+                out.push('\n');
+            }
+            let marker = if message.idx == hit.message_idx {
+                " (hit)"
+            } else {
+                ""
+            };
+            out.push_str(&format!(
+                "**#{} {}{}**\n\n",
+                message.idx, message.role, marker
+            ));
+            out.push_str(&quote(&message.content));
+        }
+    }
+    out
+}
+
+fn render_jsonl(hits: &[HitWithContext]) -> Result<String> {
+    let mut out = String::new();
+    for hit in hits {
+        out.push_str(&serde_json::to_string(hit)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    score: f32,
+    date: String,
+    session: &'a str,
+    message_idx: i32,
+    role: &'a str,
+    title: Option<&'a str>,
+    workspace: Option<&'a str>,
+    source: &'a str,
+    snippet: &'a str,
+    conversation_id: &'a str,
+    message_id: &'a str,
+    transcript: Option<&'a str>,
+    open: String,
+}
+
+fn render_csv(hits: &[HitWithContext]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for HitWithContext { hit, .. } in hits {
+        writer.serialize(CsvRow {
+            score: hit.score,
+            date: hit.timestamp().to_rfc3339(),
+            session: session_id(hit),
+            message_idx: hit.message_idx,
+            role: &hit.role,
+            title: hit.title.as_deref(),
+            workspace: hit.workspace.as_deref(),
+            source: &hit.source_id,
+            snippet: &hit.snippet,
+            conversation_id: &hit.conversation_id,
+            message_id: &hit.message_id,
+            transcript: hit.source_path.as_deref(),
+            open: show_command(hit),
+        })?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+fn session_id(hit: &HstrySearchHit) -> &str {
+    hit.external_id.as_deref().unwrap_or(&hit.conversation_id)
+}
+
+/// The command that opens the hit in its conversation.
+fn show_command(hit: &HstrySearchHit) -> String {
+    format!(
+        "agntz search show {} --from {} --to {}",
+        session_id(hit),
+        (hit.message_idx - 2).max(0),
+        hit.message_idx + 2
+    )
+}
+
+fn quote(text: &str) -> String {
+    let mut out = String::new();
+    for line in text.trim().lines() {
+        if line.trim().is_empty() {
+            out.push_str(">\n");
+        } else {
+            out.push_str(&format!("> {}\n", line));
+        }
+    }
+    out
+}
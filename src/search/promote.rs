@@ -0,0 +1,181 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::fs;
+use std::path::PathBuf;
+
+use super::HstrySearchHit;
+use crate::hstry;
+use crate::issues::{self, IssuesCommand};
+use crate::memory::{self, MemoryCommand};
+use crate::text;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum PromoteTarget {
+    Memory,
+    Task,
+}
+
+/// The message being promoted, and where it came from.
+struct Source {
+    conversation_id: String,
+    session: Option<String>,
+    idx: i32,
+    role: String,
+    content: String,
+    workspace: Option<String>,
+    adapter: Option<String>,
+}
+
+impl Source {
+    fn from_hit(hit: HstrySearchHit) -> Source {
+        Source {
+            conversation_id: hit.conversation_id,
+            session: hit.external_id,
+            idx: hit.message_idx,
+            role: hit.role,
+            content: hit.content,
+            workspace: hit.workspace,
+            adapter: Some(hit.source_adapter),
+        }
+    }
+
+    /// `sess-1 #3`, the way search prints it.
+    fn label(&self) -> String {
+        format!(
+            "{} #{}",
+            self.session.as_deref().unwrap_or(&self.conversation_id),
+            self.idx
+        )
+    }
+
+    fn provenance(&self) -> String {
+        let mut out = format!("From {} message {}", self.role, self.label());
+        let mut details = vec![format!("conversation {}", self.conversation_id)];
+        details.extend(self.adapter.clone());
+        details.extend(self.workspace.clone());
+        out.push_str(&format!(" ({})", details.join(", ")));
+        out
+    }
+}
+
+pub async fn run(
+    message: &str,
+    to: PromoteTarget,
+    title: Option<String>,
+    category: Option<String>,
+) -> Result<()> {
+    let source = resolve(message)?;
+    let content = source.content.trim().to_string();
+    if content.is_empty() {
+        anyhow::bail!("message {} has no text to promote", source.label());
+    }
+
+    match to {
+        PromoteTarget::Memory => {
+            let mut tags = vec![
+                "promoted".to_string(),
+                format!("conversation:{}", source.conversation_id),
+            ];
+            tags.extend(source.session.as_ref().map(|s| format!("session:{}", s)));
+            memory::handle(MemoryCommand::Add {
+                content: format!("{}\n\n{}", content, source.provenance()),
+                category,
+                tags: Some(tags.join(",")),
+                importance: None,
+                kind: Default::default(),
+                links: Default::default(),
+                allow_secrets: false,
+            })
+            .await
+        }
+        PromoteTarget::Task => {
+            let title = title.unwrap_or_else(|| text::first_line(&content, 80));
+            let session = source.session.as_deref().unwrap_or(&source.conversation_id);
+            let description = format!(
+                "{}\n\n{}\nRead it with: agntz search show {} --from {} --to {}",
+                content,
+                source.provenance(),
+                session,
+                source.idx,
+                source.idx
+            );
+            issues::handle(Some(IssuesCommand::Create {
+                title,
+                r#type: "task".to_string(),
+                priority: 2,
+                description: Some(description),
+            }))
+            .await
+        }
+    }
+}
+
+/// Find a message by the ID from the last search, or as `<session>#<idx>`.
+fn resolve(message: &str) -> Result<Source> {
+    if let Some(hit) = load_last()?
+        .into_iter()
+        .find(|hit| hit.message_id == message)
+    {
+        return Ok(Source::from_hit(hit));
+    }
+
+    let Some((session, idx)) = message
+        .rsplit_once('#')
+        .and_then(|(s, i)| Some((s.trim(), i.trim().parse::<i32>().ok()?)))
+    else {
+        anyhow::bail!(
+            "message {} is not in the last search results; search again, or pass <session>#<idx>",
+            message
+        );
+    };
+
+    let conversation =
+        hstry::find(session).with_context(|| format!("could not load conversation {}", session))?;
+    let found = conversation
+        .messages
+        .iter()
+        .find(|m| m.idx == idx)
+        .with_context(|| format!("conversation {} has no message #{}", session, idx))?;
+    Ok(Source {
+        conversation_id: conversation.id.clone(),
+        session: conversation.external_id.clone(),
+        idx,
+        role: found.role.clone(),
+        content: found.content.clone(),
+        workspace: conversation.workspace.clone(),
+        adapter: conversation.source_adapter.clone(),
+    })
+}
+
+/// Keep the hits of the last search, so they can be promoted by message ID.
+pub fn remember(hits: &[HstrySearchHit]) {
+    if hits.is_empty() {
+        return;
+    }
+    let result = last_path().and_then(|path| {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string(hits)?)?;
+        Ok(())
+    });
+    if let Err(err) = result {
+        eprintln!("warning: could not save search results: {:#}", err);
+    }
+}
+
+fn load_last() -> Result<Vec<HstrySearchHit>> {
+    let path = last_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(&path)?;
+    Ok(serde_json::from_str(&content).unwrap_or_default())
+}
+
+fn last_path() -> Result<PathBuf> {
+    let state = dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .context("could not determine state directory")?;
+    Ok(state.join("agntz").join("search").join("last.json"))
+}
@@ -0,0 +1,172 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use super::{HstrySearchHit, SearchArgs};
+use crate::config::Config;
+use crate::schedule::{self, ScheduleCommand};
+
+/// Seen message IDs kept per saved search; older ones are dropped first.
+const MAX_SEEN: usize = 2000;
+
+/// A search kept in the config under `[search.saved.<name>]`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SavedSearch {
+    /// Directory it was saved in, which decides the default workspace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+    /// Cron expression it is registered with skdlr under, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+    #[serde(flatten)]
+    pub args: SearchArgs,
+}
+
+/// What `run --new` has already reported.
+#[derive(Default, Deserialize, Serialize)]
+struct Seen {
+    last_run: Option<DateTime<Utc>>,
+    message_ids: Vec<String>,
+}
+
+pub async fn save(name: String, args: SearchArgs, schedule: Option<String>) -> Result<()> {
+    // Names end up in skdlr job names and state file names
+    let valid = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if name.is_empty() || !valid || name.starts_with(['-', '.']) {
+        anyhow::bail!(
+            "invalid name '{}': use letters, digits, '-', '_' and '.', not first '-' or '.'",
+            name
+        );
+    }
+
+    let replaced = Config::load()?.search.saved.contains_key(&name);
+    Config::save_search(
+        &name,
+        &SavedSearch {
+            dir: std::env::current_dir().ok(),
+            schedule: schedule.clone(),
+            args,
+        },
+    )?;
+    if replaced {
+        println!("Updated saved search '{}'", name);
+    } else {
+        println!("Saved search '{}'", name);
+    }
+
+    if let Some(schedule) = schedule {
+        schedule::handle(ScheduleCommand::Add {
+            name: schedule_name(&name),
+            schedule,
+            command: format!("agntz search run {} --new", name),
+            workdir: std::env::current_dir()
+                .ok()
+                .map(|dir| dir.to_string_lossy().to_string()),
+            description: Some(format!("New hits for saved search '{}'", name)),
+            disabled: false,
+        })
+        .await?;
+    }
+    Ok(())
+}
+
+pub fn run(name: &str, new: bool) -> Result<()> {
+    let config = Config::load()?;
+    let saved = config
+        .search
+        .saved
+        .get(name)
+        .with_context(|| format!("no saved search '{}' (see agntz search list)", name))?;
+
+    // The default workspace is the one the search was saved in
+    if let Some(dir) = saved.dir.as_ref().filter(|dir| dir.is_dir()) {
+        std::env::set_current_dir(dir)?;
+    }
+    super::run(saved.args.clone(), new.then_some(name))
+}
+
+pub fn list(json: bool) -> Result<()> {
+    let config = Config::load()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&config.search.saved)?);
+        return Ok(());
+    }
+    if config.search.saved.is_empty() {
+        println!("No saved searches.");
+        return Ok(());
+    }
+
+    for (name, saved) in &config.search.saved {
+        let mut line = format!(
+            "{} \"{}\"",
+            name,
+            saved.args.query.as_deref().unwrap_or_default()
+        );
+        if let Some(dir) = &saved.dir {
+            line.push_str(&format!(" in {}", dir.display()));
+        }
+        if let Some(schedule) = &saved.schedule {
+            line.push_str(&format!(" [{}]", schedule));
+        }
+        if let Some(last_run) = load_seen(name).ok().and_then(|seen| seen.last_run) {
+            line.push_str(&format!(
+                ", last checked {}",
+                last_run.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+            ));
+        }
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Messages an earlier `run --new` already reported.
+pub fn seen_ids(name: &str) -> Result<HashSet<String>> {
+    Ok(load_seen(name)?.message_ids.into_iter().collect())
+}
+
+/// Remember `hits` as reported.
+pub fn mark_seen(name: &str, hits: &[HstrySearchHit]) -> Result<()> {
+    let mut seen = load_seen(name)?;
+    seen.message_ids
+        .extend(hits.iter().map(|hit| hit.message_id.clone()));
+    let excess = seen.message_ids.len().saturating_sub(MAX_SEEN);
+    seen.message_ids.drain(..excess);
+    seen.last_run = Some(Utc::now());
+
+    let path = seen_path(name)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string(&seen)?)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn load_seen(name: &str) -> Result<Seen> {
+    let path = seen_path(name)?;
+    if !path.exists() {
+        return Ok(Seen::default());
+    }
+    let content = fs::read_to_string(&path)?;
+    Ok(serde_json::from_str(&content).unwrap_or_default())
+}
+
+fn seen_path(name: &str) -> Result<PathBuf> {
+    let state = dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .context("could not determine state directory")?;
+    Ok(state
+        .join("agntz")
+        .join("search")
+        .join("saved")
+        .join(format!("{}.json", name.replace('/', "%2F"))))
+}
+
+/// skdlr job for a saved search.
+fn schedule_name(name: &str) -> String {
+    format!("search-{}", name)
+}
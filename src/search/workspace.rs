@@ -0,0 +1,217 @@
+use anyhow::{Context, Result};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The workspace a search defaults to: every checkout of the current repo.
+///
+/// Sessions are recorded with the directory the agent ran in, which may be
+/// another worktree or a separate clone of the same repo. Worktrees are known
+/// up front and passed to hstry; other clones can only be recognized by the
+/// origin of a hit's workspace. `subdir` narrows each checkout to one
+/// subproject.
+pub struct WorkspaceScope {
+    /// Worktrees of this repo (or the current directory outside git)
+    roots: Vec<PathBuf>,
+    /// Normalized origin URL, to recognize other clones
+    origin: Option<String>,
+    subdir: PathBuf,
+    /// Whether sessions in subdirectories of the checkouts count too
+    nested: bool,
+    /// Checked workspace paths, as other clones' roots if they are one
+    clones: RefCell<HashMap<String, Option<PathBuf>>>,
+}
+
+impl WorkspaceScope {
+    /// The scope for the current directory, narrowed to `scope` if given;
+    /// `nested` takes in sessions run anywhere below the checkouts.
+    pub fn current(scope: Option<&Path>, nested: bool) -> Result<WorkspaceScope> {
+        let cwd = std::env::current_dir()?;
+        let Some(toplevel) = git(&cwd, &["rev-parse", "--show-toplevel"]).map(PathBuf::from) else {
+            // Outside a repo there's no telling which directories belong
+            // together, so only the directory itself counts
+            let root = match scope {
+                Some(scope) => cwd.join(scope),
+                None => cwd,
+            };
+            return Ok(WorkspaceScope {
+                roots: vec![root],
+                origin: None,
+                subdir: PathBuf::new(),
+                nested,
+                clones: RefCell::default(),
+            });
+        };
+
+        let subdir = match scope {
+            Some(scope) => {
+                let dir = cwd
+                    .join(scope)
+                    .canonicalize()
+                    .with_context(|| format!("no such directory {}", scope.display()))?;
+                let top = toplevel.canonicalize().unwrap_or_else(|_| toplevel.clone());
+                dir.strip_prefix(&top)
+                    .ok()
+                    .with_context(|| format!("{} is outside the repo", scope.display()))?
+                    .to_path_buf()
+            }
+            None => PathBuf::new(),
+        };
+
+        let mut roots: Vec<PathBuf> = git(&cwd, &["worktree", "list", "--porcelain"])
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.strip_prefix("worktree "))
+            .map(PathBuf::from)
+            .collect();
+        if !roots.contains(&toplevel) {
+            roots.push(toplevel);
+        }
+
+        Ok(WorkspaceScope {
+            roots,
+            origin: git(&cwd, &["remote", "get-url", "origin"]).map(|url| normalize_origin(&url)),
+            subdir,
+            nested,
+            clones: RefCell::default(),
+        })
+    }
+
+    /// The workspace of each worktree, as hstry's `--workspace` takes it.
+    pub fn workspaces(&self) -> Vec<String> {
+        self.roots
+            .iter()
+            .map(|root| self.within(root).to_string_lossy().to_string())
+            .collect()
+    }
+
+    /// Whether the scope reaches past what `workspaces` names exactly, so
+    /// hits have to be matched here.
+    pub fn nested(&self) -> bool {
+        self.nested
+    }
+
+    /// Whether other clones may exist, which only `is_clone` can find.
+    pub fn has_clones(&self) -> bool {
+        self.origin.is_some()
+    }
+
+    pub fn matches(&self, workspace: &str) -> bool {
+        self.in_roots(workspace) || self.is_clone(workspace)
+    }
+
+    /// Whether `workspace` is in a clone of this repo other than its own
+    /// worktrees.
+    pub fn is_clone(&self, workspace: &str) -> bool {
+        let Some(origin) = &self.origin else {
+            return false;
+        };
+        if self.in_roots(workspace) {
+            return false;
+        }
+        let path = Path::new(workspace);
+        let mut clones = self.clones.borrow_mut();
+        let clone_root = clones.entry(workspace.to_string()).or_insert_with(|| {
+            // Only checkouts on this machine can be asked for their origin
+            let dir = path.is_dir().then_some(path)?;
+            let url = git(dir, &["remote", "get-url", "origin"])?;
+            if normalize_origin(&url) != *origin {
+                return None;
+            }
+            git(dir, &["rev-parse", "--show-toplevel"]).map(PathBuf::from)
+        });
+        clone_root
+            .as_ref()
+            .is_some_and(|root| self.covers(root, path))
+    }
+
+    fn in_roots(&self, workspace: &str) -> bool {
+        let path = Path::new(workspace);
+        self.roots.iter().any(|root| self.covers(root, path))
+    }
+
+    fn covers(&self, root: &Path, path: &Path) -> bool {
+        let dir = self.within(root);
+        if self.nested {
+            path.starts_with(&dir)
+        } else {
+            path == dir
+        }
+    }
+
+    fn within(&self, root: &Path) -> PathBuf {
+        if self.subdir.as_os_str().is_empty() {
+            root.to_path_buf()
+        } else {
+            root.join(&self.subdir)
+        }
+    }
+}
+
+/// `git@github.com:Owner/repo.git` and `https://github.com/owner/repo` both
+/// become `github.com/owner/repo`. Users and ports are dropped.
+fn normalize_origin(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/').trim_end_matches(".git");
+    let (host, path) = match url.split_once("://") {
+        Some((_, rest)) => {
+            let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+            let host = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+            (host.split_once(':').map_or(host, |(h, _)| h), path)
+        }
+        // scp-like `user@host:path`, or a local path
+        None => match url.split_once(':') {
+            Some((host, path)) => (host.rsplit_once('@').map_or(host, |(_, h)| h), path),
+            None => return url.to_lowercase(),
+        },
+    };
+    format!("{}/{}", host, path.trim_start_matches('/')).to_lowercase()
+}
+
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let out = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!out.is_empty()).then_some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_origin_matches_ssh_and_https() {
+        let expected = "github.com/owner/repo";
+        for url in [
+            "git@github.com:Owner/repo.git",
+            "https://github.com/owner/repo",
+            "https://github.com/owner/repo.git/",
+            "https://user@github.com/owner/repo.git",
+            "ssh://git@github.com/owner/repo.git",
+            "ssh://git@github.com:22/owner/repo",
+            "  git@github.com:owner/repo\n",
+        ] {
+            assert_eq!(normalize_origin(url), expected, "{}", url);
+        }
+    }
+
+    #[test]
+    fn normalize_origin_keeps_hosts_and_paths_apart() {
+        assert_ne!(
+            normalize_origin("git@github.com:owner/repo.git"),
+            normalize_origin("git@gitlab.com:owner/repo.git")
+        );
+        assert_ne!(
+            normalize_origin("https://github.com/owner/repo"),
+            normalize_origin("https://github.com/owner/repo-2")
+        );
+        assert_eq!(normalize_origin("/srv/git/Repo.git"), "/srv/git/repo");
+    }
+}
@@ -0,0 +1,273 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use clap::Subcommand;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::activity::{self, Event};
+use crate::memory::{self, MemoryCommand};
+use crate::{agent, hstry, issues, text};
+
+#[derive(Subcommand)]
+pub enum SessionCommand {
+    /// Report what an agent did in a session
+    Summary {
+        /// Session name or ID (defaults to the current agent session)
+        #[arg(long)]
+        session: Option<String>,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+        /// Also save the summary as a memory
+        #[arg(long)]
+        save_memory: bool,
+        /// Also add the summary as a comment on this trx task
+        #[arg(long, value_name = "TASK")]
+        comment_on: Option<String>,
+    },
+}
+
+pub async fn handle(command: SessionCommand) -> Result<()> {
+    match command {
+        SessionCommand::Summary {
+            session,
+            json,
+            save_memory,
+            comment_on,
+        } => handle_summary(session, json, save_memory, comment_on).await,
+    }
+}
+
+#[derive(Serialize)]
+struct Summary {
+    session: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    started: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ended: Option<DateTime<Utc>>,
+    commands: usize,
+    /// Failed agntz commands, with their errors
+    failures: Vec<String>,
+    /// Task changes, e.g. `closed app-12`
+    tasks: Vec<String>,
+    memories: Vec<AddedMemory>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    conversation: Option<Conversation>,
+    /// Sources that could not be read
+    unavailable: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct AddedMemory {
+    id: Option<String>,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct Conversation {
+    messages: usize,
+    by_role: BTreeMap<String, usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    first_prompt: Option<String>,
+}
+
+async fn handle_summary(
+    session: Option<String>,
+    json: bool,
+    save_memory: bool,
+    comment_on: Option<String>,
+) -> Result<()> {
+    let identity = agent::detect();
+    let session = match session {
+        Some(session) => session,
+        None => identity
+            .as_ref()
+            .and_then(|i| i.session_id.clone().or_else(|| i.session.clone()))
+            .context("no agent session detected; pass --session <id>")?,
+    };
+
+    let summary = summarize(&session)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else {
+        print!("{}", render(&summary));
+    }
+
+    if save_memory {
+        memory::handle(MemoryCommand::Add {
+            content: render(&summary),
+            category: Some("session".to_string()),
+            tags: Some("session-summary".to_string()),
+            importance: None,
+            kind: Default::default(),
+            links: Default::default(),
+            allow_secrets: false,
+        })
+        .await?;
+    }
+    if let Some(task) = comment_on {
+        issues::comment(&task, &render(&summary))?;
+    }
+    Ok(())
+}
+
+fn summarize(session: &str) -> Result<Summary> {
+    let mut summary = Summary {
+        session: session.to_string(),
+        agent: None,
+        started: None,
+        ended: None,
+        commands: 0,
+        failures: Vec::new(),
+        tasks: Vec::new(),
+        memories: Vec::new(),
+        conversation: None,
+        unavailable: Vec::new(),
+    };
+
+    let entries = activity::read()?;
+    for entry in &entries {
+        let Some(agent) = entry.agent.as_ref().filter(|a| a.in_session(session)) else {
+            continue;
+        };
+        summary.agent.get_or_insert_with(|| agent.agent.clone());
+        summary.started.get_or_insert(entry.time);
+        summary.ended = Some(entry.time);
+
+        match &entry.event {
+            Event::Invocation {
+                args, ok, error, ..
+            } => {
+                summary.commands += 1;
+                if !ok {
+                    summary.failures.push(format!(
+                        "agntz {}: {}",
+                        args.join(" "),
+                        error.as_deref().unwrap_or("failed")
+                    ));
+                }
+            }
+            Event::Backend { backend, args, ok } if backend == "trx" && *ok => {
+                if let Some(change) = describe_task_change(args) {
+                    summary.tasks.push(change);
+                }
+            }
+            Event::Backend { .. } => {}
+        }
+    }
+
+    match memory::fetch_memories(false) {
+        Ok(memories) => {
+            summary.memories = memories
+                .into_iter()
+                .filter(|m| m.in_session(session))
+                .map(|m| AddedMemory {
+                    id: m.id,
+                    content: text::first_line(&m.content, 100),
+                })
+                .collect();
+        }
+        Err(_) => summary.unavailable.push("memories (mmry)".to_string()),
+    }
+
+    match hstry::messages(session) {
+        Ok(messages) => {
+            let mut by_role = BTreeMap::new();
+            for message in &messages {
+                *by_role.entry(message.role.clone()).or_insert(0) += 1;
+            }
+            summary.conversation = Some(Conversation {
+                messages: messages.len(),
+                by_role,
+                first_prompt: messages
+                    .iter()
+                    .find(|m| m.role == "user")
+                    .map(|m| text::first_line(&m.content, 200)),
+            });
+        }
+        Err(_) => summary.unavailable.push("conversation (hstry)".to_string()),
+    }
+
+    Ok(summary)
+}
+
+/// `created "Fix login"`, `updated app-12 (--status in_progress)`, `closed app-12`
+fn describe_task_change(args: &[String]) -> Option<String> {
+    let (command, rest) = args.split_first()?;
+    let target = rest.first()?;
+    let options = rest[1..].join(" ");
+    Some(match command.as_str() {
+        "create" => format!("created \"{}\"", target),
+        "update" if !options.is_empty() => format!("updated {} ({})", target, options),
+        "update" => format!("updated {}", target),
+        "close" => format!("closed {}", target),
+        "comment" => format!("commented on {}", target),
+        _ => return None,
+    })
+}
+
+fn render(summary: &Summary) -> String {
+    let mut out = format!("Session {}", summary.session);
+    if let Some(agent) = &summary.agent {
+        out.push_str(&format!(" ({})", agent));
+    }
+    if let (Some(started), Some(ended)) = (summary.started, summary.ended) {
+        out.push_str(&format!(
+            ", {} to {}",
+            started.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            ended.with_timezone(&Local).format("%H:%M")
+        ));
+    }
+    out.push('\n');
+    out.push_str(&format!(
+        "{} agntz commands, {} failed\n",
+        summary.commands,
+        summary.failures.len()
+    ));
+
+    if let Some(conversation) = &summary.conversation {
+        let roles = conversation
+            .by_role
+            .iter()
+            .map(|(role, count)| format!("{} {}", count, role))
+            .collect::<Vec<_>>()
+            .join(", ");
+        out.push_str(&format!("{} messages ({})\n", conversation.messages, roles));
+        if let Some(prompt) = &conversation.first_prompt {
+            out.push_str(&format!("Started with: {}\n", prompt));
+        }
+    }
+
+    let sections: [(&str, Vec<String>); 3] = [
+        ("Tasks", summary.tasks.clone()),
+        (
+            "Memories added",
+            summary
+                .memories
+                .iter()
+                .map(|m| format!("{} {}", m.id.as_deref().unwrap_or("-"), m.content))
+                .collect(),
+        ),
+        ("Failures", summary.failures.clone()),
+    ];
+    for (title, items) in sections {
+        if items.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n{}:\n", title));
+        for item in items {
+            out.push_str(&format!("- {}\n", item));
+        }
+    }
+
+    if !summary.unavailable.is_empty() {
+        out.push_str(&format!(
+            "\nNot available: {}\n",
+            summary.unavailable.join(", ")
+        ));
+    }
+    out
+}
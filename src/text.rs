@@ -0,0 +1,21 @@
+/// Cut `text` to at most `max_len` characters, ending in `...` when cut.
+pub fn truncate(text: &str, max_len: usize) -> String {
+    if text.chars().count() <= max_len {
+        return text.to_string();
+    }
+    let mut trimmed: String = text.chars().take(max_len.saturating_sub(3)).collect();
+    trimmed.push_str("...");
+    trimmed
+}
+
+/// `text` on one line, with runs of whitespace collapsed, cut to `max_len`.
+pub fn compact(text: &str, max_len: usize) -> String {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    truncate(&collapsed, max_len)
+}
+
+/// The first non-blank line of `text`, cut to `max_len`.
+pub fn first_line(text: &str, max_len: usize) -> String {
+    let line = text.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+    truncate(line.trim(), max_len)
+}
@@ -0,0 +1,93 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+
+/// Parse a point in time given on the command line.
+///
+/// Accepts RFC 3339 timestamps, plain dates (`2026-03-01`, midnight UTC) and
+/// relative ages counted back from now (`30m`, `12h`, `7d`, `2w`).
+pub fn parse_time(value: &str) -> Result<DateTime<Utc>> {
+    parse_time_at(value, Utc::now())
+}
+
+fn parse_time_at(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+
+    let Some(unit) = value.chars().last() else {
+        anyhow::bail!("empty time value");
+    };
+    let amount: i64 = value[..value.len() - unit.len_utf8()]
+        .parse()
+        .with_context(|| format!("invalid time '{}' (use 2026-03-01, 7d, 12h, ...)", value))?;
+    let age = match unit {
+        'm' => TimeDelta::try_minutes(amount),
+        'h' => TimeDelta::try_hours(amount),
+        'd' => TimeDelta::try_days(amount),
+        'w' => TimeDelta::try_weeks(amount),
+        _ => anyhow::bail!("invalid time '{}' (use 2026-03-01, 7d, 12h, ...)", value),
+    };
+    age.and_then(|age| now.checked_sub_signed(age))
+        .with_context(|| format!("time '{}' is out of range", value))
+}
+
+/// Parse a timestamp as stored by the wrapped tools, which is RFC 3339 in
+/// practice but occasionally lacks a timezone.
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|ts| ts.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+                .or_else(|_| chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f"))
+                .map(|ts| ts.and_utc())
+                .ok()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-03-10T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn parses_absolute_times() {
+        let ts = parse_time_at("2026-03-01T08:30:00+01:00", now()).unwrap();
+        assert_eq!(ts.to_rfc3339(), "2026-03-01T07:30:00+00:00");
+        let date = parse_time_at(" 2026-03-01 ", now()).unwrap();
+        assert_eq!(date.to_rfc3339(), "2026-03-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn parses_relative_ages() {
+        let ago = |value| now() - parse_time_at(value, now()).unwrap();
+        assert_eq!(ago("30m"), TimeDelta::minutes(30));
+        assert_eq!(ago("12h"), TimeDelta::hours(12));
+        assert_eq!(ago("7d"), TimeDelta::days(7));
+        assert_eq!(ago("2w"), TimeDelta::weeks(2));
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        for value in ["", "d", "7", "7y", "seven days", "2026-13-01"] {
+            assert!(parse_time_at(value, now()).is_err(), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn rejects_ages_out_of_range() {
+        for value in ["100000000d", "99999999999999999d", "9223372036854775807m"] {
+            assert!(parse_time_at(value, now()).is_err(), "{:?}", value);
+        }
+    }
+}
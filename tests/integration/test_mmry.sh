@@ -69,6 +69,14 @@ MMRY_STORE="$STORE" $AGNTZ memory export -o /tmp/test_export.json &> /dev/null &
 echo -n "Test 8: memory export markdown... "
 MMRY_STORE="$STORE" $AGNTZ memory export -o /tmp/test_export.md -f md &> /dev/null && echo "✓" || echo "✗"
 
+# Test 8b: Export and re-import other formats
+for FMT in jsonl csv obsidian; do
+    echo -n "Test 8b: memory export/import $FMT... "
+    MMRY_STORE="$STORE" $AGNTZ memory export -o /tmp/test_export_$FMT -f $FMT &> /dev/null \
+        && MMRY_STORE="$STORE" $AGNTZ memory import /tmp/test_export_$FMT -f $FMT &> /dev/null \
+        && echo "✓" || echo "✗"
+done
+
 # Test 9: Add another memory with tags
 echo -n "Test 9: memory add with tags... "
 MMRY_STORE="$STORE" $AGNTZ memory add "another memory" -c "test" -t "tag1,tag2" &> /dev/null && echo "✓" || echo "✗"
@@ -79,7 +87,7 @@ $AGNTZ memory stores &> /dev/null && echo "✓" || echo "✗"
 
 # Cleanup
 echo -n "Cleanup: removing test data... "
-rm -f /tmp/test_export.json /tmp/test_export.md
+rm -rf /tmp/test_export.json /tmp/test_export.md /tmp/test_export_*
 rm -rf ~/.local/share/mmry/stores/$STORE 2>/dev/null || true
 echo "✓"
 